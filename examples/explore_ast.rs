@@ -33,52 +33,49 @@ class UserController {
     for (i, statement) in program.statements.iter().enumerate() {
         println!("\nStatement {}: {:?}", i, std::mem::discriminant(statement));
 
-        match statement {
-            Statement::Namespace(ns) => {
-                println!("  Namespace found");
-                println!("    Name: {:?}", ns.name);
-                println!("    Has {} statements", ns.statements().len());
+        if let Statement::Namespace(ns) = statement {
+            println!("  Namespace found");
+            println!("    Name: {:?}", ns.name);
+            println!("    Has {} statements", ns.statements().len());
 
-                for (j, stmt) in ns.statements().iter().enumerate() {
-                    println!("    Statement {}: {:?}", j, std::mem::discriminant(stmt));
+            for (j, stmt) in ns.statements().iter().enumerate() {
+                println!("    Statement {}: {:?}", j, std::mem::discriminant(stmt));
 
-                    if let Statement::Use(use_stmt) = stmt {
-                        println!("      USE STATEMENT: {:?}", use_stmt);
-                    }
+                if let Statement::Use(use_stmt) = stmt {
+                    println!("      USE STATEMENT: {:?}", use_stmt);
+                }
 
-                    if let Statement::Class(class) = stmt {
-                        println!("      Class name: {:?}", class.name);
-                        println!("      Has extends: {}", class.extends.is_some());
-                        println!("      Has implements: {}", class.implements.is_some());
+                if let Statement::Class(class) = stmt {
+                    println!("      Class name: {:?}", class.name);
+                    println!("      Has extends: {}", class.extends.is_some());
+                    println!("      Has implements: {}", class.implements.is_some());
 
-                        if let Some(extends) = &class.extends {
-                            println!("      Extends: {:?}", extends);
-                        }
+                    if let Some(extends) = &class.extends {
+                        println!("      Extends: {:?}", extends);
+                    }
 
-                        if let Some(implements) = &class.implements {
-                            println!("      Implements: {} interfaces", implements.types.len());
-                        }
+                    if let Some(implements) = &class.implements {
+                        println!("      Implements: {} interfaces", implements.types.len());
+                    }
 
-                        println!("      Has {} members", class.members.len());
-                        for (k, member) in class.members.iter().enumerate() {
-                            println!("        Member {}: {:?}", k, std::mem::discriminant(member));
-                            match member {
-                                ClassLikeMember::Property(prop) => {
-                                    println!("          Property: {:?}", prop);
-                                }
-                                ClassLikeMember::Method(method) => {
-                                    println!("          Method params: {} parameters", method.parameter_list.parameters.len());
-                                    if !method.parameter_list.parameters.is_empty() {
-                                        println!("          First param: {:?}", method.parameter_list.parameters.first());
-                                    }
+                    println!("      Has {} members", class.members.len());
+                    for (k, member) in class.members.iter().enumerate() {
+                        println!("        Member {}: {:?}", k, std::mem::discriminant(member));
+                        match member {
+                            ClassLikeMember::Property(prop) => {
+                                println!("          Property: {:?}", prop);
+                            }
+                            ClassLikeMember::Method(method) => {
+                                println!("          Method params: {} parameters", method.parameter_list.parameters.len());
+                                if !method.parameter_list.parameters.is_empty() {
+                                    println!("          First param: {:?}", method.parameter_list.parameters.first());
                                 }
-                                _ => {}
                             }
+                            _ => {}
                         }
                     }
                 }
             }
-            _ => {}
         }
     }
 }
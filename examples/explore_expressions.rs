@@ -21,18 +21,15 @@ class Test {
 }
 "#;
 
-    let result = parse_file_content(&arena, FileId::zero(), code.as_bytes());
+    let (program, error) = parse_file_content(&arena, FileId::zero(), code);
 
-    match result {
-        Ok(program) => {
-            println!("=== AST Structure ===\n");
-            for statement in program.statements.iter() {
-                explore_statement(statement, 0);
-            }
-        }
-        Err(e) => {
-            eprintln!("Parse error: {:?}", e);
-        }
+    if let Some(err) = error {
+        eprintln!("Parse error: {:?}", err);
+    }
+
+    println!("=== AST Structure ===\n");
+    for statement in program.statements.iter() {
+        explore_statement(statement, 0);
     }
 }
 
@@ -59,31 +56,23 @@ fn explore_statement(stmt: &Statement, indent: usize) {
 fn explore_member(member: &ClassLikeMember, indent: usize) {
     let prefix = "  ".repeat(indent);
 
-    match member {
-        ClassLikeMember::Method(method) => {
-            println!("{}Method: {}", prefix, method.name.value);
-            println!("{}  Body present: {}", prefix, method.body.is_some());
+    if let ClassLikeMember::Method(method) = member {
+        println!("{}Method: {}", prefix, method.name.value);
+        println!("{}  Body present: {}", prefix, matches!(method.body, MethodBody::Concrete(_)));
 
-            if let Some(body) = &method.body {
-                println!("{}  Body type: FunctionLikeBody", prefix);
-                explore_body(body, indent + 1);
-            }
+        if let MethodBody::Concrete(block) = &method.body {
+            println!("{}  Body type: Block", prefix);
+            explore_body(block, indent + 1);
         }
-        _ => {}
     }
 }
 
-fn explore_body(body: &FunctionLikeBody, indent: usize) {
+fn explore_body(block: &Block, indent: usize) {
     let prefix = "  ".repeat(indent);
 
-    match body {
-        FunctionLikeBody::Block(block) => {
-            println!("{}Block with {} statements", prefix, block.statements.len());
-            for stmt in block.statements.iter() {
-                explore_body_statement(stmt, indent + 1);
-            }
-        }
-        _ => {}
+    println!("{}Block with {} statements", prefix, block.statements.len());
+    for stmt in block.statements.iter() {
+        explore_body_statement(stmt, indent + 1);
     }
 }
 
@@ -93,11 +82,11 @@ fn explore_body_statement(stmt: &Statement, indent: usize) {
     match stmt {
         Statement::Expression(expr_stmt) => {
             println!("{}ExpressionStatement", prefix);
-            explore_expression(&expr_stmt.expression, indent + 1);
+            explore_expression(expr_stmt.expression, indent + 1);
         }
         Statement::If(if_stmt) => {
             println!("{}If", prefix);
-            explore_expression(&if_stmt.condition, indent + 1);
+            explore_expression(if_stmt.condition, indent + 1);
         }
         Statement::Try(try_stmt) => {
             println!("{}Try", prefix);
@@ -119,29 +108,29 @@ fn explore_expression(expr: &Expression, indent: usize) {
             println!("{}Instantiation", prefix);
             println!("{}  Class: {:?}", prefix, inst.class);
         }
-        Expression::StaticMethodCall(call) => {
+        Expression::Call(Call::StaticMethod(call)) => {
             println!("{}StaticMethodCall", prefix);
             println!("{}  Class: {:?}", prefix, call.class);
             println!("{}  Method: {:?}", prefix, call.method);
         }
-        Expression::StaticPropertyFetch(fetch) => {
+        Expression::Access(Access::StaticProperty(fetch)) => {
             println!("{}StaticPropertyFetch", prefix);
             println!("{}  Class: {:?}", prefix, fetch.class);
         }
-        Expression::Instanceof(inst) => {
+        Expression::Binary(binary) if matches!(binary.operator, BinaryOperator::Instanceof(_)) => {
             println!("{}Instanceof", prefix);
-            explore_expression(&inst.left, indent + 1);
-            explore_expression(&inst.right, indent + 1);
+            explore_expression(binary.lhs, indent + 1);
+            explore_expression(binary.rhs, indent + 1);
         }
         Expression::Identifier(id) => {
             println!("{}Identifier: {}", prefix, id.value());
         }
         Expression::Variable(var) => {
-            println!("{}Variable: {:?}", prefix, var.name);
+            println!("{}Variable: {:?}", prefix, var);
         }
         Expression::Assignment(assign) => {
             println!("{}Assignment", prefix);
-            explore_expression(&assign.right, indent + 1);
+            explore_expression(assign.rhs, indent + 1);
         }
         _ => {
             println!("{}{:?}", prefix, std::mem::discriminant(expr));
@@ -149,8 +138,8 @@ fn explore_expression(expr: &Expression, indent: usize) {
     }
 }
 
-fn explore_catch_clause(clause: &CatchClause, indent: usize) {
+fn explore_catch_clause(clause: &TryCatchClause, indent: usize) {
     let prefix = "  ".repeat(indent);
     println!("{}CatchClause", prefix);
-    println!("{}  Types: {:?}", prefix, clause.types);
+    println!("{}  Types: {:?}", prefix, clause.hint);
 }
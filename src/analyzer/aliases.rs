@@ -0,0 +1,128 @@
+use crate::analyzer::extractor::method_symbol_name;
+use crate::model::{AliasUsage, CodeModel, ReferenceKind};
+use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Read an alias map: a JSON object of legacy class names to the classes they
+/// are aliases of, e.g. `{"Old_Invoice": "Billing\\Invoice"}`
+pub fn load_alias_map(path: &Path) -> Result<HashMap<String, String>> {
+    let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Point references and calls made through a class alias at the class it names,
+/// and record each reference as an alias usage. Aliases come from `class_alias()`
+/// calls in the analyzed code and from `configured`, which wins when both name
+/// the same alias.
+pub fn resolve_aliases(model: &mut CodeModel, configured: &HashMap<String, String>) {
+    let mut aliases: HashMap<String, String> = HashMap::new();
+    for alias in model.files.iter().flat_map(|file| file.aliases.iter()) {
+        aliases.insert(alias.alias.to_lowercase(), alias.class.clone());
+    }
+    for (alias, class) in configured {
+        aliases.insert(
+            alias.trim_start_matches('\\').to_lowercase(),
+            class.trim_start_matches('\\').to_string(),
+        );
+    }
+    if aliases.is_empty() {
+        return;
+    }
+
+    let mut usages = Vec::new();
+    for file in &mut model.files {
+        // Declared parents are reported through their `extends` references
+        for parent in file.symbols.iter_mut().flat_map(|symbol| symbol.parents.iter_mut()) {
+            if let Some(class) = canonical_class(&aliases, parent) {
+                *parent = class;
+            }
+        }
+        for reference in file.references.iter_mut().chain(file.class_strings.iter_mut()) {
+            if reference.kind == ReferenceKind::FunctionCall {
+                continue;
+            }
+            let Some(class) = canonical_class(&aliases, &reference.to) else {
+                continue;
+            };
+            usages.push(AliasUsage {
+                from: reference.from.clone(),
+                alias: std::mem::replace(&mut reference.to, class.clone()),
+                class,
+                location: reference.location.clone(),
+            });
+        }
+        for call in &mut file.calls {
+            let Some((class, method)) = call.to.rsplit_once("::") else {
+                continue;
+            };
+            if let Some(class) = canonical_class(&aliases, class) {
+                call.to = method_symbol_name(&class, method);
+            }
+        }
+    }
+    model.alias_usages = usages;
+}
+
+/// The class an alias names, following aliases of aliases
+fn canonical_class(aliases: &HashMap<String, String>, name: &str) -> Option<String> {
+    let mut class = aliases.get(&name.to_lowercase())?;
+    let mut seen = HashSet::from([name.to_lowercase()]);
+    while let Some(next) = aliases.get(&class.to_lowercase()) {
+        if !seen.insert(class.to_lowercase()) {
+            break;
+        }
+        class = next;
+    }
+    Some(class.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::extractor::extract_source;
+
+    #[test]
+    fn test_resolves_references_through_aliases() {
+        let mut model = CodeModel::new();
+        model.add_file(extract_source("src/Billing/Invoice.php", r#"<?php
+namespace Billing;
+class Invoice { public static function create() {} }
+class_alias(Invoice::class, 'Old_Invoice');
+class_alias('Old_Invoice', 'Ancient_Invoice');
+"#));
+        model.add_file(extract_source("legacy/report.php", r#"<?php
+class Legacy_Report extends Legacy_Base {
+    public function run(Old_Invoice $invoice) {
+        Ancient_Invoice::create();
+        return new \Billing\Invoice();
+    }
+}
+"#));
+
+        let configured = HashMap::from([("Legacy_Base".to_string(), "\\Reports\\Base".to_string())]);
+        resolve_aliases(&mut model, &configured);
+
+        let refs: Vec<_> = model.files[1].references.iter().map(|r| (r.to.as_str(), r.kind)).collect();
+        assert_eq!(refs, vec![
+            ("Reports\\Base", ReferenceKind::Extends),
+            ("Billing\\Invoice", ReferenceKind::ParameterHint),
+            ("Billing\\Invoice", ReferenceKind::StaticCall),
+            ("Billing\\Invoice", ReferenceKind::Instantiation),
+        ]);
+        assert_eq!(model.files[1].calls[0].to, "Billing\\Invoice::create");
+
+        let usages: Vec<_> = model
+            .alias_usages
+            .iter()
+            .map(|u| (u.alias.as_str(), u.class.as_str(), u.location.line))
+            .collect();
+        assert_eq!(usages, vec![
+            ("Legacy_Base", "Reports\\Base", 2),
+            ("Old_Invoice", "Billing\\Invoice", 3),
+            ("Ancient_Invoice", "Billing\\Invoice", 4),
+        ]);
+        // The alias declarations are not usages of the aliases
+        assert!(model.files[0].references.iter().all(|r| !r.to.ends_with("_Invoice")));
+    }
+}
@@ -0,0 +1,546 @@
+use crate::analyzer::extractor::method_symbol_name;
+use crate::model::CodeModel;
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+/// Classes, interfaces and enums defined by PHP itself and its commonly bundled
+/// extensions, by fully qualified name
+const BUILTIN_CLASSES: &[&str] = &[
+    // Core
+    "stdClass",
+    "Traversable",
+    "Iterator",
+    "IteratorAggregate",
+    "ArrayAccess",
+    "Countable",
+    "Serializable",
+    "Stringable",
+    "JsonSerializable",
+    "UnitEnum",
+    "BackedEnum",
+    "Closure",
+    "Generator",
+    "WeakMap",
+    "WeakReference",
+    "Fiber",
+    "Attribute",
+    "ReturnTypeWillChange",
+    "AllowDynamicProperties",
+    "SensitiveParameter",
+    "SensitiveParameterValue",
+    "Override",
+    "Deprecated",
+    "__PHP_Incomplete_Class",
+    "InternalIterator",
+    // Exceptions and errors
+    "Throwable",
+    "Exception",
+    "ErrorException",
+    "Error",
+    "CompileError",
+    "ParseError",
+    "TypeError",
+    "ArgumentCountError",
+    "ValueError",
+    "ArithmeticError",
+    "DivisionByZeroError",
+    "UnhandledMatchError",
+    "FiberError",
+    "ClosedGeneratorException",
+    "JsonException",
+    // SPL exceptions
+    "LogicException",
+    "BadFunctionCallException",
+    "BadMethodCallException",
+    "DomainException",
+    "InvalidArgumentException",
+    "LengthException",
+    "OutOfRangeException",
+    "RuntimeException",
+    "OutOfBoundsException",
+    "OverflowException",
+    "RangeException",
+    "UnderflowException",
+    "UnexpectedValueException",
+    // SPL data structures, iterators and files
+    "ArrayObject",
+    "ArrayIterator",
+    "RecursiveArrayIterator",
+    "SplDoublyLinkedList",
+    "SplQueue",
+    "SplStack",
+    "SplHeap",
+    "SplMinHeap",
+    "SplMaxHeap",
+    "SplPriorityQueue",
+    "SplFixedArray",
+    "SplObjectStorage",
+    "SplObserver",
+    "SplSubject",
+    "SplFileInfo",
+    "SplFileObject",
+    "SplTempFileObject",
+    "DirectoryIterator",
+    "FilesystemIterator",
+    "RecursiveDirectoryIterator",
+    "GlobIterator",
+    "OuterIterator",
+    "RecursiveIterator",
+    "SeekableIterator",
+    "AppendIterator",
+    "CachingIterator",
+    "RecursiveCachingIterator",
+    "CallbackFilterIterator",
+    "RecursiveCallbackFilterIterator",
+    "EmptyIterator",
+    "FilterIterator",
+    "RecursiveFilterIterator",
+    "ParentIterator",
+    "InfiniteIterator",
+    "IteratorIterator",
+    "LimitIterator",
+    "MultipleIterator",
+    "NoRewindIterator",
+    "RecursiveIteratorIterator",
+    "RecursiveTreeIterator",
+    "RegexIterator",
+    "RecursiveRegexIterator",
+    // Date
+    "DateTimeInterface",
+    "DateTime",
+    "DateTimeImmutable",
+    "DateTimeZone",
+    "DateInterval",
+    "DatePeriod",
+    "DateError",
+    "DateObjectError",
+    "DateRangeError",
+    "DateException",
+    "DateInvalidTimeZoneException",
+    "DateInvalidOperationException",
+    "DateMalformedStringException",
+    "DateMalformedIntervalStringException",
+    "DateMalformedPeriodStringException",
+    // Reflection
+    "Reflector",
+    "Reflection",
+    "ReflectionException",
+    "ReflectionClass",
+    "ReflectionObject",
+    "ReflectionEnum",
+    "ReflectionMethod",
+    "ReflectionFunction",
+    "ReflectionFunctionAbstract",
+    "ReflectionParameter",
+    "ReflectionProperty",
+    "ReflectionClassConstant",
+    "ReflectionEnumUnitCase",
+    "ReflectionEnumBackedCase",
+    "ReflectionType",
+    "ReflectionNamedType",
+    "ReflectionUnionType",
+    "ReflectionIntersectionType",
+    "ReflectionAttribute",
+    "ReflectionGenerator",
+    "ReflectionFiber",
+    "ReflectionReference",
+    "ReflectionExtension",
+    "ReflectionZendExtension",
+    // PDO
+    "PDO",
+    "PDOStatement",
+    "PDOException",
+    "PDORow",
+    // MySQLi
+    "mysqli",
+    "mysqli_result",
+    "mysqli_stmt",
+    "mysqli_driver",
+    "mysqli_warning",
+    "mysqli_sql_exception",
+    // DOM, SimpleXML and XML
+    "DOMNode",
+    "DOMDocument",
+    "DOMElement",
+    "DOMAttr",
+    "DOMText",
+    "DOMComment",
+    "DOMCdataSection",
+    "DOMCharacterData",
+    "DOMDocumentFragment",
+    "DOMDocumentType",
+    "DOMEntity",
+    "DOMEntityReference",
+    "DOMNotation",
+    "DOMProcessingInstruction",
+    "DOMNodeList",
+    "DOMNamedNodeMap",
+    "DOMXPath",
+    "DOMImplementation",
+    "DOMException",
+    "DOMParentNode",
+    "DOMChildNode",
+    "Dom\\Node",
+    "Dom\\Document",
+    "Dom\\HTMLDocument",
+    "Dom\\XMLDocument",
+    "Dom\\Element",
+    "Dom\\HTMLElement",
+    "Dom\\NodeList",
+    "Dom\\XPath",
+    "SimpleXMLElement",
+    "SimpleXMLIterator",
+    "XMLReader",
+    "XMLWriter",
+    "XSLTProcessor",
+    "LibXMLError",
+    // Intl
+    "Collator",
+    "NumberFormatter",
+    "Normalizer",
+    "Locale",
+    "MessageFormatter",
+    "IntlDateFormatter",
+    "IntlDatePatternGenerator",
+    "ResourceBundle",
+    "Transliterator",
+    "IntlCalendar",
+    "IntlGregorianCalendar",
+    "IntlTimeZone",
+    "IntlBreakIterator",
+    "IntlRuleBasedBreakIterator",
+    "IntlCodePointBreakIterator",
+    "IntlPartsIterator",
+    "IntlIterator",
+    "IntlChar",
+    "IntlException",
+    "Spoofchecker",
+    "UConverter",
+    // Random
+    "Random\\Randomizer",
+    "Random\\Engine",
+    "Random\\CryptoSafeEngine",
+    "Random\\Engine\\Mt19937",
+    "Random\\Engine\\PcgOneseq128XslRr64",
+    "Random\\Engine\\Xoshiro256StarStar",
+    "Random\\Engine\\Secure",
+    "Random\\IntervalBoundary",
+    "Random\\RandomError",
+    "Random\\BrokenRandomEngineError",
+    "Random\\RandomException",
+    // Other bundled extensions
+    "CURLFile",
+    "CURLStringFile",
+    "CurlHandle",
+    "CurlMultiHandle",
+    "CurlShareHandle",
+    "ZipArchive",
+    "PharData",
+    "Phar",
+    "PharFileInfo",
+    "PharException",
+    "SessionHandler",
+    "SessionHandlerInterface",
+    "SessionIdInterface",
+    "SessionUpdateTimestampHandlerInterface",
+    "SoapClient",
+    "SoapServer",
+    "SoapFault",
+    "SoapHeader",
+    "SoapParam",
+    "SoapVar",
+    "finfo",
+    "GdImage",
+    "GdFont",
+    "GMP",
+    "BcMath\\Number",
+    "OpenSSLCertificate",
+    "OpenSSLCertificateSigningRequest",
+    "OpenSSLAsymmetricKey",
+    "SodiumException",
+    "HashContext",
+    "Socket",
+    "AddressInfo",
+    "php_user_filter",
+    "Directory",
+    "SQLite3",
+    "SQLite3Stmt",
+    "SQLite3Result",
+    "SQLite3Exception",
+];
+
+/// Functions defined by PHP itself and its commonly bundled extensions. Calls to
+/// other undeclared functions, such as framework helpers, are external.
+const BUILTIN_FUNCTIONS: &[&str] = &[
+    // Core, function and class handling
+    "strlen", "strcmp", "strncmp", "strcasecmp", "strncasecmp", "error_reporting", "define", "defined",
+    "constant", "func_get_args", "func_get_arg", "func_num_args", "function_exists", "class_exists",
+    "interface_exists", "trait_exists", "enum_exists", "method_exists", "property_exists", "get_class",
+    "get_parent_class", "get_called_class", "get_object_vars", "get_class_vars", "get_class_methods",
+    "is_subclass_of", "is_a", "class_implements", "class_parents", "class_uses", "spl_autoload_register",
+    "spl_autoload_unregister", "spl_autoload_functions", "spl_autoload_call", "spl_object_hash",
+    "spl_object_id", "iterator_to_array", "iterator_count", "iterator_apply", "call_user_func",
+    "call_user_func_array", "forward_static_call", "forward_static_call_array", "is_callable",
+    "register_shutdown_function", "get_defined_functions", "get_defined_vars", "get_defined_constants",
+    "get_declared_classes", "get_declared_interfaces", "get_declared_traits", "get_resource_type",
+    "get_resource_id", "get_resources", "gc_collect_cycles", "gc_enable", "gc_disable", "gc_enabled",
+    "trigger_error", "user_error", "set_error_handler", "restore_error_handler", "set_exception_handler",
+    "restore_exception_handler", "error_log", "error_get_last", "error_clear_last", "debug_backtrace",
+    "debug_print_backtrace", "debug_zval_refcount", "extension_loaded", "get_loaded_extensions",
+    "get_extension_funcs", "dl", "zend_version",
+    // Variables and types
+    "var_dump", "var_export", "print_r", "serialize", "unserialize", "debug_zval_dump", "gettype",
+    "get_debug_type", "settype", "intval", "floatval", "doubleval", "boolval", "strval", "is_null", "is_int",
+    "is_integer", "is_long", "is_float", "is_double", "is_string", "is_bool", "is_array", "is_object",
+    "is_numeric", "is_scalar", "is_iterable", "is_countable", "is_resource", "array_key_exists",
+    // Strings
+    "addslashes", "addcslashes", "stripslashes", "stripcslashes", "bin2hex", "hex2bin", "chr", "ord",
+    "chunk_split", "convert_uuencode", "convert_uudecode", "count_chars", "crc32", "crypt", "explode",
+    "implode", "join", "html_entity_decode", "htmlentities", "htmlspecialchars", "htmlspecialchars_decode",
+    "get_html_translation_table", "lcfirst", "ucfirst", "ucwords", "levenshtein", "similar_text", "soundex",
+    "metaphone", "ltrim", "rtrim", "trim", "chop", "md5", "md5_file", "sha1", "sha1_file", "nl2br",
+    "number_format", "parse_str", "printf", "sprintf", "vprintf", "vsprintf", "fprintf", "vfprintf", "sscanf",
+    "quotemeta", "quoted_printable_decode", "quoted_printable_encode", "str_contains", "str_starts_with",
+    "str_ends_with", "str_getcsv", "str_ireplace", "str_replace", "str_pad", "str_repeat", "str_rot13",
+    "str_shuffle", "str_split", "str_word_count", "strcoll", "strcspn", "strip_tags", "stripos", "stristr",
+    "strrev", "strnatcmp", "strnatcasecmp", "strpbrk", "strpos", "strrchr", "strripos", "strrpos", "strspn",
+    "strstr", "strtok", "strtolower", "strtoupper", "strtr", "substr", "substr_compare", "substr_count",
+    "substr_replace", "wordwrap", "nl_langinfo", "localeconv", "setlocale", "uniqid", "lcg_value",
+    // Multibyte strings
+    "mb_strlen", "mb_substr", "mb_strpos", "mb_strrpos", "mb_stripos", "mb_strripos", "mb_strstr", "mb_stristr",
+    "mb_strrchr", "mb_strtolower", "mb_strtoupper", "mb_convert_case", "mb_convert_encoding",
+    "mb_detect_encoding", "mb_check_encoding", "mb_internal_encoding", "mb_substr_count", "mb_str_split",
+    "mb_str_pad", "mb_strwidth", "mb_strimwidth", "mb_trim", "mb_ltrim", "mb_rtrim", "mb_ucfirst", "mb_lcfirst",
+    "mb_encode_mimeheader", "mb_decode_mimeheader", "mb_list_encodings", "mb_ord", "mb_chr", "mb_scrub",
+    "mb_regex_encoding", "mb_ereg", "mb_eregi", "mb_ereg_replace", "mb_split", "mb_parse_str", "mb_language",
+    "mb_http_output", "mb_substitute_character",
+    // Iconv and ctype
+    "iconv", "iconv_strlen", "iconv_substr", "iconv_strpos", "iconv_strrpos", "iconv_get_encoding",
+    "iconv_set_encoding", "iconv_mime_encode", "iconv_mime_decode", "ctype_alnum", "ctype_alpha", "ctype_cntrl",
+    "ctype_digit", "ctype_graph", "ctype_lower", "ctype_print", "ctype_punct", "ctype_space", "ctype_upper",
+    "ctype_xdigit",
+    // Regular expressions
+    "preg_match", "preg_match_all", "preg_replace", "preg_replace_callback", "preg_replace_callback_array",
+    "preg_filter", "preg_split", "preg_quote", "preg_grep", "preg_last_error", "preg_last_error_msg",
+    // Arrays
+    "array_change_key_case", "array_chunk", "array_column", "array_combine", "array_count_values", "array_diff",
+    "array_diff_assoc", "array_diff_key", "array_diff_uassoc", "array_diff_ukey", "array_fill",
+    "array_fill_keys", "array_filter", "array_find", "array_find_key", "array_any", "array_all", "array_flip",
+    "array_intersect", "array_intersect_assoc", "array_intersect_key", "array_intersect_uassoc",
+    "array_intersect_ukey", "array_is_list", "array_key_first", "array_key_last", "array_keys", "array_map",
+    "array_merge", "array_merge_recursive", "array_multisort", "array_pad", "array_pop", "array_product",
+    "array_push", "array_rand", "array_reduce", "array_replace", "array_replace_recursive", "array_reverse",
+    "array_search", "array_shift", "array_slice", "array_splice", "array_sum", "array_udiff",
+    "array_udiff_assoc", "array_udiff_uassoc", "array_uintersect", "array_uintersect_assoc",
+    "array_uintersect_uassoc", "array_unique", "array_unshift", "array_values", "array_walk",
+    "array_walk_recursive", "arsort", "asort", "compact", "count", "sizeof", "current", "pos", "end", "extract",
+    "in_array", "key", "key_exists", "krsort", "ksort", "natcasesort", "natsort", "next", "prev", "range",
+    "reset", "rsort", "shuffle", "sort", "uasort", "uksort", "usort",
+    // Math
+    "abs", "acos", "acosh", "asin", "asinh", "atan", "atan2", "atanh", "base_convert", "bindec", "ceil", "cos",
+    "cosh", "decbin", "dechex", "decoct", "deg2rad", "exp", "expm1", "fdiv", "floor", "fmod", "fpow", "hexdec",
+    "hypot", "intdiv", "is_finite", "is_infinite", "is_nan", "log", "log10", "log1p", "log2", "max", "min",
+    "octdec", "pi", "pow", "rad2deg", "round", "sin", "sinh", "sqrt", "tan", "tanh", "mt_rand", "mt_srand",
+    "mt_getrandmax", "rand", "srand", "getrandmax", "random_bytes", "random_int", "bcadd", "bcsub", "bcmul",
+    "bcdiv", "bcmod", "bcpow", "bcsqrt", "bccomp", "bcscale", "bcpowmod", "gmp_add", "gmp_sub", "gmp_mul",
+    "gmp_init", "gmp_strval", "gmp_cmp",
+    // Date and time
+    "time", "mktime", "gmmktime", "date", "gmdate", "idate", "getdate", "localtime", "strftime", "gmstrftime",
+    "strtotime", "checkdate", "date_default_timezone_get", "date_default_timezone_set", "date_create",
+    "date_create_immutable", "date_create_from_format", "date_create_immutable_from_format", "date_parse",
+    "date_parse_from_format", "date_diff", "date_add", "date_sub", "date_format", "date_interval_format",
+    "date_timestamp_get", "date_timestamp_set", "date_timezone_get", "date_timezone_set", "timezone_open",
+    "timezone_identifiers_list", "timezone_name_from_abbr", "microtime", "hrtime", "usleep", "sleep",
+    "time_nanosleep", "time_sleep_until", "gettimeofday",
+    // JSON and serialization
+    "json_encode", "json_decode", "json_last_error", "json_last_error_msg", "json_validate",
+    // Files and streams
+    "basename", "dirname", "pathinfo", "realpath", "file_exists", "is_file", "is_dir", "is_link", "is_readable",
+    "is_writable", "is_writeable", "is_executable", "is_uploaded_file", "move_uploaded_file", "file",
+    "file_get_contents", "file_put_contents", "fopen", "fclose", "fread", "fwrite", "fputs", "fgets", "fgetc",
+    "fgetcsv", "fputcsv", "feof", "fflush", "fseek", "ftell", "rewind", "ftruncate", "flock", "fstat", "fscanf",
+    "fpassthru", "readfile", "tmpfile", "tempnam", "sys_get_temp_dir", "mkdir", "rmdir", "unlink", "rename",
+    "copy", "touch", "chmod", "chown", "chgrp", "umask", "stat", "lstat", "clearstatcache", "filemtime",
+    "fileatime", "filectime", "filesize", "filetype", "fileperms", "fileowner", "filegroup", "fileinode",
+    "disk_free_space", "disk_total_space", "glob", "fnmatch", "opendir", "readdir", "closedir", "rewinddir",
+    "scandir", "dir", "getcwd", "chdir", "chroot", "link", "symlink", "readlink", "parse_ini_file",
+    "parse_ini_string", "stream_context_create", "stream_context_set_option", "stream_context_get_options",
+    "stream_get_contents", "stream_get_meta_data", "stream_set_blocking", "stream_set_timeout", "stream_select",
+    "stream_socket_client", "stream_socket_server", "stream_socket_accept", "stream_wrapper_register",
+    "stream_filter_append", "stream_filter_prepend", "stream_filter_register", "stream_copy_to_stream",
+    "stream_is_local", "stream_resolve_include_path", "fsockopen", "pfsockopen", "set_include_path",
+    "get_include_path",
+    // Output and execution
+    "ob_start", "ob_get_clean", "ob_get_contents", "ob_end_clean", "ob_end_flush", "ob_flush", "ob_get_flush",
+    "ob_get_length", "ob_get_level", "ob_get_status", "ob_implicit_flush", "ob_list_handlers", "flush",
+    "header", "headers_sent", "headers_list", "header_remove", "http_response_code", "setcookie",
+    "setrawcookie", "exec", "shell_exec", "system", "passthru", "proc_open", "proc_close", "proc_terminate",
+    "proc_get_status", "proc_nice", "popen", "pclose", "escapeshellarg", "escapeshellcmd", "highlight_string",
+    "highlight_file", "php_strip_whitespace",
+    // Runtime and environment
+    "ini_get", "ini_set", "ini_get_all", "ini_restore", "ini_parse_quantity", "set_time_limit",
+    "ignore_user_abort", "connection_aborted", "connection_status", "getenv", "putenv", "php_sapi_name",
+    "php_uname", "phpversion", "phpinfo", "phpcredits", "memory_get_usage", "memory_get_peak_usage",
+    "memory_reset_peak_usage", "getmypid", "getmyuid", "getmygid", "getmyinode", "getlastmod",
+    "get_current_user", "gethostname", "gethostbyname", "gethostbynamel", "gethostbyaddr", "getrusage",
+    "sys_getloadavg", "version_compare", "get_cfg_var", "cli_set_process_title", "cli_get_process_title",
+    "assert", "assert_options", "opcache_get_status", "opcache_reset", "opcache_invalidate",
+    "opcache_compile_file",
+    // URLs, hashing and encoding
+    "urlencode", "urldecode", "rawurlencode", "rawurldecode", "http_build_query", "parse_url", "get_headers",
+    "get_meta_tags", "base64_encode", "base64_decode", "hash", "hash_algos", "hash_file", "hash_hmac",
+    "hash_hmac_algos", "hash_init", "hash_update", "hash_final", "hash_copy", "hash_equals", "hash_pbkdf2",
+    "hash_hkdf", "password_hash", "password_verify", "password_needs_rehash", "password_get_info",
+    "password_algos", "openssl_encrypt", "openssl_decrypt", "openssl_random_pseudo_bytes", "openssl_sign",
+    "openssl_verify", "openssl_pkey_get_private", "openssl_pkey_get_public", "openssl_digest",
+    "openssl_cipher_iv_length", "sodium_crypto_secretbox", "sodium_crypto_secretbox_open", "sodium_crypto_box",
+    "sodium_randombytes_buf", "sodium_bin2hex", "sodium_hex2bin", "sodium_memzero",
+    // Filter, session and mail
+    "filter_var", "filter_input", "filter_var_array", "filter_input_array", "filter_has_var", "filter_list",
+    "filter_id", "session_start", "session_destroy", "session_id", "session_regenerate_id", "session_name",
+    "session_status", "session_write_close", "session_unset", "session_get_cookie_params",
+    "session_set_cookie_params", "session_set_save_handler", "mail",
+    // Other bundled extensions
+    "curl_init", "curl_setopt", "curl_setopt_array", "curl_exec", "curl_close", "curl_error", "curl_errno",
+    "curl_getinfo", "curl_multi_init", "curl_multi_add_handle", "curl_multi_exec", "curl_multi_remove_handle",
+    "curl_multi_close", "curl_reset", "gzcompress", "gzuncompress", "gzencode", "gzdecode", "gzdeflate",
+    "gzinflate", "gzopen", "gzread", "gzwrite", "gzclose", "zlib_encode", "zlib_decode",
+    "simplexml_load_string", "simplexml_load_file", "libxml_use_internal_errors", "libxml_get_errors",
+    "libxml_clear_errors", "xml_parser_create", "xml_parse", "xml_parser_free", "mysqli_connect",
+    "mysqli_query", "mysqli_fetch_assoc", "mysqli_real_escape_string", "mysqli_close", "pg_connect", "pg_query",
+    "pg_fetch_assoc", "pg_escape_string", "pcntl_fork", "pcntl_signal", "pcntl_waitpid", "posix_getpid",
+    "posix_kill", "socket_create", "socket_connect", "socket_read", "socket_write", "socket_close", "gettext",
+    "_", "ngettext", "bindtextdomain", "textdomain", "image_type_to_mime_type", "getimagesize",
+    "imagecreatetruecolor", "imagecreatefromstring", "imagepng", "imagejpeg", "imagedestroy", "exif_read_data",
+    "finfo_open", "finfo_file", "finfo_close", "mime_content_type", "apcu_fetch", "apcu_store", "apcu_delete",
+];
+
+/// Built-in names keyed by their lowercase form, as PHP class names are case-insensitive
+fn catalogue() -> &'static HashMap<String, &'static str> {
+    static CATALOGUE: OnceLock<HashMap<String, &'static str>> = OnceLock::new();
+    CATALOGUE.get_or_init(|| {
+        BUILTIN_CLASSES
+            .iter()
+            .map(|name| (name.to_lowercase(), *name))
+            .collect()
+    })
+}
+
+/// Canonical spelling of a built-in class, or `None` if the name is not one
+pub fn lookup(fqn: &str) -> Option<&'static str> {
+    catalogue()
+        .get(&fqn.trim_start_matches('\\').to_lowercase())
+        .copied()
+}
+
+/// Built-in function names, all lowercase, as PHP function names are case-insensitive
+fn function_catalogue() -> &'static HashSet<&'static str> {
+    static CATALOGUE: OnceLock<HashSet<&'static str>> = OnceLock::new();
+    CATALOGUE.get_or_init(|| BUILTIN_FUNCTIONS.iter().copied().collect())
+}
+
+/// Whether a reference target is a built-in class, or a built-in function such as
+/// `strlen()`
+pub fn is_builtin(fqn: &str) -> bool {
+    match fqn.trim_start_matches('\\').strip_suffix("()") {
+        Some(function) => function_catalogue().contains(function.to_lowercase().as_str()),
+        None => lookup(fqn).is_some(),
+    }
+}
+
+/// Point references and method calls that resolved a built-in's name into the
+/// referencing class's own namespace, such as an unimported `Exception` in `App\Models`, at the
+/// built-in instead. PHP only falls back to the global namespace for functions
+/// and constants, so that code works only if the namespace defines the class; when
+/// no analyzed file does, the built-in is what was meant.
+pub fn apply_fallbacks(model: &mut CodeModel) {
+    let defined: HashSet<String> = model.symbols().map(|s| s.fqn.clone()).collect();
+    // Namespace each symbol is declared in, as script symbols are named after their file
+    let namespaces: HashMap<String, Option<String>> = model
+        .symbols()
+        .map(|s| (s.fqn.clone(), s.namespace.clone()))
+        .collect();
+    for file in &mut model.files {
+        for symbol in &mut file.symbols {
+            for parent in &mut symbol.parents {
+                if let Some(builtin) = fallback(parent, symbol.namespace.as_deref(), &defined) {
+                    *parent = builtin.to_string();
+                }
+            }
+        }
+        for reference in &mut file.references {
+            let from_namespace = namespaces.get(&reference.from).and_then(|ns| ns.as_deref());
+            if let Some(builtin) = fallback(&reference.to, from_namespace, &defined) {
+                reference.to = builtin.to_string();
+            }
+        }
+        for call in &mut file.calls {
+            // Callers are methods, named `Class::method`, or functions and scripts
+            let caller = namespaces
+                .get(&call.from)
+                .or_else(|| namespaces.get(call.from.rsplit_once("::")?.0));
+            let from_namespace = caller.and_then(|ns| ns.as_deref());
+            let Some((class, method)) = call.to.rsplit_once("::") else {
+                continue;
+            };
+            if let Some(builtin) = fallback(class, from_namespace, &defined) {
+                call.to = method_symbol_name(builtin, method);
+            }
+        }
+    }
+}
+
+/// The built-in a class name resolved in `namespace` stands for, if it is not
+/// defined in the analyzed code
+fn fallback(name: &str, namespace: Option<&str>, defined: &HashSet<String>) -> Option<&'static str> {
+    if defined.contains(name) {
+        return None;
+    }
+    // Built-ins take their canonical casing, e.g. `\datetime` becomes `DateTime`
+    if let Some(builtin) = lookup(name) {
+        return (builtin != name).then_some(builtin);
+    }
+    let (name_namespace, short_name) = name.rsplit_once('\\')?;
+    if namespace != Some(name_namespace) {
+        return None;
+    }
+    lookup(short_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_is_case_insensitive() {
+        assert_eq!(lookup("\\datetimeimmutable"), Some("DateTimeImmutable"));
+        assert_eq!(lookup("Random\\Randomizer"), Some("Random\\Randomizer"));
+        assert!(is_builtin("Closure"));
+        assert!(!is_builtin("App\\Exception"));
+        assert!(is_builtin("strlen()"));
+        assert!(is_builtin("\\Array_Map()"));
+        assert!(!is_builtin("App\\helper()"));
+        assert!(!is_builtin("collect()"));
+        assert!(!is_builtin("dd()"));
+    }
+
+    #[test]
+    fn test_falls_back_to_builtins_not_defined_in_the_namespace() {
+        use crate::analyzer::extractor::extract_source;
+
+        let mut model = CodeModel::new();
+        model.add_file(extract_source(
+            "src/Models/User.php",
+            "<?php\nnamespace App\\Models;\nclass User {\n    public function f(Locale $l): \\datetimeimmutable { throw new Exception(); }\n}\n",
+        ));
+        model.add_file(extract_source("src/Models/Locale.php", "<?php\nnamespace App\\Models;\nclass Locale {}\n"));
+        // Scripts are named after their file, so their namespace comes from the model
+        model.add_file(extract_source("src/boot.php", "<?php\nnamespace App;\nthrow new Exception(strlen('x'));\n"));
+
+        model.resolve_function_fallbacks();
+        apply_fallbacks(&mut model);
+
+        let targets: Vec<_> = model.references().map(|r| r.to.as_str()).collect();
+        assert_eq!(targets, vec!["DateTimeImmutable", "App\\Models\\Locale", "Exception", "Exception", "strlen()"]);
+    }
+}
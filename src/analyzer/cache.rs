@@ -0,0 +1,96 @@
+use crate::model::FileModel;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+/// Default cache location, relative to the working directory
+pub const DEFAULT_CACHE_DIR: &str = ".modfather-cache";
+
+/// Sources defining the cached `FileModel` layout and the extraction rules that
+/// fill it. They are hashed into every key, so entries written by a build whose
+/// model or extractor differs are never reused.
+const EXTRACTION_SOURCES: &[&str] = &[
+    include_str!("../model/mod.rs"),
+    include_str!("extractor.rs"),
+    include_str!("docblock.rs"),
+    include_str!("php_parser.rs"),
+];
+
+/// On-disk cache of per-file extraction results. Each entry is a JSON file named
+/// after a hash of the tool version, the extraction sources, the file path and
+/// its content, so a changed file or a new build simply misses.
+pub struct AnalysisCache {
+    dir: PathBuf,
+}
+
+impl AnalysisCache {
+    pub fn open(dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create cache directory: {}", dir.display()))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+        })
+    }
+
+    /// Cache key for a file. The path is part of the key because extracted
+    /// symbols and locations record it.
+    pub fn key(path: &str, content: &str) -> String {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(env!("CARGO_PKG_VERSION").as_bytes());
+        for source in EXTRACTION_SOURCES {
+            hasher.update(blake3::hash(source.as_bytes()).as_bytes());
+        }
+        hasher.update(path.as_bytes());
+        hasher.update(&[0]);
+        hasher.update(content.as_bytes());
+        hasher.finalize().to_hex().to_string()
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+
+    /// Cached extraction result, if any. Unreadable or corrupt entries count as a miss.
+    pub fn get(&self, key: &str) -> Option<FileModel> {
+        let content = std::fs::read(self.entry_path(key)).ok()?;
+        serde_json::from_slice(&content).ok()
+    }
+
+    pub fn put(&self, key: &str, file: &FileModel) -> Result<()> {
+        let path = self.entry_path(key);
+        // Write to a temporary file first so concurrent runs never read a partial entry
+        let tmp = self.dir.join(format!("{}.{}.tmp", key, std::process::id()));
+        std::fs::write(&tmp, serde_json::to_vec(file)?)
+            .with_context(|| format!("Failed to write cache entry: {}", tmp.display()))?;
+        std::fs::rename(&tmp, &path)
+            .with_context(|| format!("Failed to write cache entry: {}", path.display()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::extractor::extract_source;
+
+    #[test]
+    fn test_round_trips_file_models() {
+        let dir = std::env::temp_dir().join("php-modfather-cache-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache = AnalysisCache::open(&dir).unwrap();
+
+        let content = "<?php\nnamespace App;\nclass User extends Model {}\n";
+        let key = AnalysisCache::key("src/User.php", content);
+        assert!(cache.get(&key).is_none());
+
+        let file = extract_source("src/User.php", content);
+        cache.put(&key, &file).unwrap();
+
+        let cached = cache.get(&key).unwrap();
+        assert_eq!(cached.symbols, file.symbols);
+        assert_eq!(cached.references, file.references);
+
+        // A different path or content is a different entry
+        assert_ne!(key, AnalysisCache::key("src/Other.php", content));
+        assert_ne!(key, AnalysisCache::key("src/User.php", "<?php\n"));
+    }
+}
@@ -0,0 +1,252 @@
+use crate::analyzer::psr4::Psr4Mapping;
+use crate::model::{Package, PackageIndex};
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// A directory or file Composer autoloads for the root project
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceRoot {
+    pub path: PathBuf,
+    /// Declared under `autoload-dev` rather than `autoload`
+    pub dev: bool,
+}
+
+/// Autoload configuration of a Composer project and the packages it has installed
+#[derive(Debug, Default)]
+pub struct ComposerProject {
+    pub source_roots: Vec<SourceRoot>,
+    pub packages: PackageIndex,
+    /// PSR-4 prefixes of the root project, with directories relative to the
+    /// working directory
+    pub psr4: Vec<Psr4Mapping>,
+}
+
+impl ComposerProject {
+    /// Load a project from its `composer.json`. Installed packages are read from
+    /// `vendor/composer/installed.json`, falling back to `composer.lock`.
+    pub fn load(composer_json: &Path) -> Result<Self> {
+        let root = composer_json.parent().unwrap_or(Path::new(""));
+        let manifest = read_json(composer_json)?;
+        let name = manifest
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or("__root__")
+            .to_string();
+
+        let mut project = Self::default();
+        for (section, dev) in [("autoload", false), ("autoload-dev", true)] {
+            let Some(autoload) = manifest.get(section) else {
+                continue;
+            };
+            let package = Package { name: name.clone(), dev };
+
+            for (prefix, dirs) in standard_prefixes(autoload, "psr-4") {
+                let dirs = dirs.iter().map(|dir| root.join(dir).to_string_lossy().into_owned()).collect();
+                project.psr4.push(Psr4Mapping::new(&prefix, dirs));
+            }
+            for (prefix, dirs) in autoload_prefixes(autoload) {
+                project.packages.add_prefix(&prefix, package.clone());
+                for dir in dirs {
+                    project.add_root(root.join(dir), dev);
+                }
+            }
+            for path in autoload_classmap(autoload) {
+                project.add_root(root.join(path), dev);
+            }
+        }
+
+        let vendor_dir = manifest
+            .pointer("/config/vendor-dir")
+            .and_then(Value::as_str)
+            .unwrap_or("vendor");
+        let installed = root.join(vendor_dir).join("composer").join("installed.json");
+        let lock = root.join("composer.lock");
+        if installed.is_file() {
+            project.load_installed(&read_json(&installed)?);
+        } else if lock.is_file() {
+            project.load_lock(&read_json(&lock)?);
+        }
+
+        Ok(project)
+    }
+
+    fn add_root(&mut self, path: PathBuf, dev: bool) {
+        if !self.source_roots.iter().any(|r| r.path == path) {
+            self.source_roots.push(SourceRoot { path, dev });
+        }
+    }
+
+    /// Composer 2 writes `{"packages": [...], "dev-package-names": [...]}`,
+    /// Composer 1 a bare array of packages
+    fn load_installed(&mut self, installed: &Value) {
+        let (packages, dev_names): (&[Value], Vec<&str>) = match installed {
+            Value::Array(packages) => (packages, Vec::new()),
+            _ => (
+                installed.get("packages").and_then(Value::as_array).map(Vec::as_slice).unwrap_or(&[]),
+                installed
+                    .get("dev-package-names")
+                    .and_then(Value::as_array)
+                    .map(|names| names.iter().filter_map(Value::as_str).collect())
+                    .unwrap_or_default(),
+            ),
+        };
+
+        for package in packages {
+            let dev = package
+                .get("name")
+                .and_then(Value::as_str)
+                .is_some_and(|name| dev_names.contains(&name));
+            self.add_package(package, dev);
+        }
+    }
+
+    fn load_lock(&mut self, lock: &Value) {
+        for (section, dev) in [("packages", false), ("packages-dev", true)] {
+            for package in lock.get(section).and_then(Value::as_array).into_iter().flatten() {
+                self.add_package(package, dev);
+            }
+        }
+    }
+
+    fn add_package(&mut self, package: &Value, dev: bool) {
+        let (Some(name), Some(autoload)) = (
+            package.get("name").and_then(Value::as_str),
+            package.get("autoload"),
+        ) else {
+            return;
+        };
+
+        for (prefix, _) in autoload_prefixes(autoload) {
+            self.packages.add_prefix(
+                &prefix,
+                Package {
+                    name: name.to_string(),
+                    dev,
+                },
+            );
+        }
+    }
+}
+
+fn read_json(path: &Path) -> Result<Value> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// PSR-4 and PSR-0 namespace prefixes with their directories. A prefix maps to a
+/// single directory or to a list of them. PSR-0 prefixes may use `_` instead of `\`
+/// and their directory holds the full namespace path, but both resolve classes by
+/// prefix so they are treated alike here.
+fn autoload_prefixes(autoload: &Value) -> Vec<(String, Vec<String>)> {
+    ["psr-4", "psr-0"]
+        .into_iter()
+        .flat_map(|standard| standard_prefixes(autoload, standard))
+        .collect()
+}
+
+/// Namespace prefixes and directories of one autoloading standard
+fn standard_prefixes(autoload: &Value, standard: &str) -> Vec<(String, Vec<String>)> {
+    let Some(map) = autoload.get(standard).and_then(Value::as_object) else {
+        return Vec::new();
+    };
+    map.iter()
+        .map(|(prefix, dirs)| {
+            let dirs = match dirs {
+                Value::String(dir) => vec![dir.clone()],
+                Value::Array(dirs) => dirs.iter().filter_map(Value::as_str).map(str::to_string).collect(),
+                _ => Vec::new(),
+            };
+            (prefix.clone(), dirs)
+        })
+        .collect()
+}
+
+fn autoload_classmap(autoload: &Value) -> Vec<String> {
+    autoload
+        .get("classmap")
+        .and_then(Value::as_array)
+        .map(|paths| paths.iter().filter_map(Value::as_str).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("php-modfather-{}", name));
+        let _ = std::fs::remove_dir_all(&dir);
+        for (path, content) in files {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn test_loads_roots_and_installed_packages() {
+        let dir = write_project("installed", &[
+            (
+                "composer.json",
+                r#"{
+                    "name": "acme/shop",
+                    "autoload": {"psr-4": {"Acme\\": ["src/", "lib/"]}, "classmap": ["legacy/"]},
+                    "autoload-dev": {"psr-4": {"Acme\\Tests\\": "tests/"}}
+                }"#,
+            ),
+            (
+                "vendor/composer/installed.json",
+                r#"{
+                    "packages": [
+                        {"name": "symfony/console", "autoload": {"psr-4": {"Symfony\\Component\\Console\\": ""}}},
+                        {"name": "phpunit/phpunit", "autoload": {"classmap": ["src/"]}},
+                        {"name": "mockery/mockery", "autoload": {"psr-0": {"Mockery": "library/"}}}
+                    ],
+                    "dev-package-names": ["phpunit/phpunit", "mockery/mockery"]
+                }"#,
+            ),
+        ]);
+
+        let project = ComposerProject::load(&dir.join("composer.json")).unwrap();
+
+        let roots: Vec<_> = project
+            .source_roots
+            .iter()
+            .map(|r| (r.path.strip_prefix(&dir).unwrap().to_str().unwrap(), r.dev))
+            .collect();
+        assert_eq!(roots, vec![("src", false), ("lib", false), ("legacy", false), ("tests", true)]);
+
+        let psr4: Vec<_> = project.psr4.iter().map(|m| (m.prefix.as_str(), m.dirs.len())).collect();
+        assert_eq!(psr4, vec![("Acme\\", 2), ("Acme\\Tests\\", 1)]);
+
+        let package = |name: &str| project.packages.lookup(name).map(|p| (p.name.as_str(), p.dev));
+        assert_eq!(package("Acme\\Cart"), Some(("acme/shop", false)));
+        assert_eq!(package("Acme\\Tests\\CartTest"), Some(("acme/shop", true)));
+        assert_eq!(package("Symfony\\Component\\Console\\Command\\Command"), Some(("symfony/console", false)));
+        assert_eq!(package("Mockery\\Mock"), Some(("mockery/mockery", true)));
+        assert_eq!(package("Psr\\Log\\LoggerInterface"), None);
+    }
+
+    #[test]
+    fn test_falls_back_to_composer_lock() {
+        let dir = write_project("lock", &[
+            ("composer.json", r#"{"autoload": {"psr-4": {"App\\": "app/"}}}"#),
+            (
+                "composer.lock",
+                r#"{
+                    "packages": [{"name": "monolog/monolog", "autoload": {"psr-4": {"Monolog\\": "src/Monolog"}}}],
+                    "packages-dev": [{"name": "fakerphp/faker", "autoload": {"psr-4": {"Faker\\": "src/Faker/"}}}]
+                }"#,
+            ),
+        ]);
+
+        let project = ComposerProject::load(&dir.join("composer.json")).unwrap();
+
+        assert_eq!(project.packages.lookup_namespace("App").map(|p| p.name.as_str()), Some("__root__"));
+        assert_eq!(project.packages.lookup("Monolog\\Logger").map(|p| p.dev), Some(false));
+        assert_eq!(project.packages.lookup("Faker\\Generator").map(|p| p.dev), Some(true));
+    }
+}
@@ -0,0 +1,203 @@
+use crate::model::{AliasUsage, CodeModel, Diagnostic, DiagnosticKind};
+use indexmap::IndexMap;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// Label of the global namespace in alias debt
+const GLOBAL_NAMESPACE: &str = "(global)";
+
+/// References through class aliases made from one namespace, each a place
+/// still to migrate to the class's current name
+#[derive(Debug, Serialize)]
+pub struct AliasDebt {
+    pub namespace: String,
+    pub usages: Vec<AliasUsage>,
+}
+
+/// Summary of the diagnostics gathered over a run
+#[derive(Debug, Serialize)]
+pub struct DiagnosticsReport {
+    pub files_analyzed: usize,
+    pub files_with_errors: usize,
+    pub diagnostics: Vec<Diagnostic>,
+    /// Alias usages by namespace, most first
+    pub alias_debt: Vec<AliasDebt>,
+}
+
+impl DiagnosticsReport {
+    pub fn from_model(model: &CodeModel) -> Self {
+        let diagnostics: Vec<Diagnostic> = model.diagnostics().cloned().collect();
+        let files_with_errors = diagnostics
+            .iter()
+            .filter(|d| d.kind == DiagnosticKind::ParseError)
+            .map(|d| d.location.file.as_str())
+            .collect::<HashSet<_>>()
+            .len();
+
+        let namespaces: HashMap<&str, &str> = model
+            .symbols()
+            .filter_map(|symbol| Some((symbol.fqn.as_str(), symbol.namespace.as_deref()?)))
+            .collect();
+        let mut by_namespace: IndexMap<String, Vec<AliasUsage>> = IndexMap::new();
+        for usage in &model.alias_usages {
+            let namespace = namespaces.get(usage.from.as_str()).copied().unwrap_or(GLOBAL_NAMESPACE);
+            by_namespace.entry(namespace.to_string()).or_default().push(usage.clone());
+        }
+        let mut alias_debt: Vec<AliasDebt> = by_namespace
+            .into_iter()
+            .map(|(namespace, usages)| AliasDebt { namespace, usages })
+            .collect();
+        alias_debt.sort_by_key(|debt| std::cmp::Reverse(debt.usages.len()));
+
+        Self {
+            files_analyzed: model.files.len(),
+            files_with_errors,
+            diagnostics,
+            alias_debt,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty() && self.alias_debt.is_empty()
+    }
+
+    fn of_kind(&self, kind: DiagnosticKind) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(move |d| d.kind == kind)
+    }
+
+    /// Format the report as human-readable text, one diagnostic per line,
+    /// grouped by kind
+    pub fn format_text(&self) -> String {
+        let mut output = String::new();
+        if self.files_with_errors > 0 {
+            output.push_str(&format!(
+                "Parse errors in {} of {} files (references in these files may be incomplete):\n",
+                self.files_with_errors, self.files_analyzed
+            ));
+            for diagnostic in self.of_kind(DiagnosticKind::ParseError) {
+                output.push_str(&format!("  {}: {}\n", diagnostic.location, diagnostic.message));
+            }
+        }
+
+        let mismatches: Vec<_> = self.of_kind(DiagnosticKind::CaseMismatch).collect();
+        if !mismatches.is_empty() {
+            output.push_str(&format!(
+                "Class name casing mismatches ({}), which break on case-sensitive autoloaders:\n",
+                mismatches.len()
+            ));
+            for diagnostic in mismatches {
+                output.push_str(&format!("  {}: {}\n", diagnostic.location, diagnostic.message));
+            }
+        }
+
+        let includes: Vec<_> = self.of_kind(DiagnosticKind::UnresolvedInclude).collect();
+        if !includes.is_empty() {
+            output.push_str(&format!("Unresolved includes ({}), left out of the file graph:\n", includes.len()));
+            for diagnostic in includes {
+                output.push_str(&format!("  {}: {}\n", diagnostic.location, diagnostic.message));
+            }
+        }
+
+        let duplicates: Vec<_> = self.of_kind(DiagnosticKind::DuplicateDefinition).collect();
+        if !duplicates.is_empty() {
+            output.push_str(&format!(
+                "Duplicate definitions ({}), of which the graphs use one:\n",
+                duplicates.len()
+            ));
+            for diagnostic in duplicates {
+                output.push_str(&format!("  {}: {}\n", diagnostic.location, diagnostic.message));
+            }
+        }
+
+        let dynamic: Vec<_> = self.of_kind(DiagnosticKind::DynamicClass).collect();
+        if !dynamic.is_empty() {
+            output.push_str(&format!(
+                "Dynamic class references ({}), whose dependencies are missing from the graph:\n",
+                dynamic.len()
+            ));
+            for diagnostic in dynamic {
+                output.push_str(&format!("  {}: {}\n", diagnostic.location, diagnostic.message));
+            }
+        }
+
+        if !self.alias_debt.is_empty() {
+            let total: usize = self.alias_debt.iter().map(|debt| debt.usages.len()).sum();
+            output.push_str(&format!("Class alias usages ({}), to migrate to the aliased class:\n", total));
+            for debt in &self.alias_debt {
+                output.push_str(&format!("  {} ({}):\n", debt.namespace, debt.usages.len()));
+                for usage in &debt.usages {
+                    output.push_str(&format!(
+                        "    {}: `{}` is an alias of `{}`\n",
+                        usage.location, usage.alias, usage.class
+                    ));
+                }
+            }
+        }
+        output
+    }
+
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{FileModel, Location};
+
+    #[test]
+    fn test_report_formats() {
+        let mut model = CodeModel::new();
+        model.add_file(FileModel::new("src/Ok.php"));
+        let mut broken = FileModel::new("src/Broken.php");
+        broken.diagnostics.push(Diagnostic {
+            kind: DiagnosticKind::ParseError,
+            location: Location {
+                file: "src/Broken.php".to_string(),
+                line: 6,
+                column: 14,
+            },
+            message: "Unexpected token `;`".to_string(),
+        });
+        broken.diagnostics.push(Diagnostic {
+            kind: DiagnosticKind::CaseMismatch,
+            location: Location {
+                file: "src/Broken.php".to_string(),
+                line: 9,
+                column: 13,
+            },
+            message: "`App\\user` is declared as `App\\User`".to_string(),
+        });
+        model.add_file(broken);
+        model.alias_usages.push(AliasUsage {
+            from: "Legacy_Report".to_string(),
+            alias: "Old_Invoice".to_string(),
+            class: "Billing\\Invoice".to_string(),
+            location: Location {
+                file: "legacy/report.php".to_string(),
+                line: 3,
+                column: 25,
+            },
+        });
+
+        let report = DiagnosticsReport::from_model(&model);
+        assert_eq!(
+            report.format_text(),
+            "Parse errors in 1 of 2 files (references in these files may be incomplete):\n  \
+             src/Broken.php:6:14: Unexpected token `;`\n\
+             Class name casing mismatches (1), which break on case-sensitive autoloaders:\n  \
+             src/Broken.php:9:13: `App\\user` is declared as `App\\User`\n\
+             Class alias usages (1), to migrate to the aliased class:\n  \
+             (global) (1):\n    \
+             legacy/report.php:3:25: `Old_Invoice` is an alias of `Billing\\Invoice`\n"
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(json["files_with_errors"], 1);
+        assert_eq!(json["diagnostics"][0]["kind"], "parse-error");
+        assert_eq!(json["diagnostics"][0]["location"]["line"], 6);
+        assert_eq!(json["diagnostics"][1]["kind"], "case-mismatch");
+        assert_eq!(json["alias_debt"][0]["usages"][0]["alias"], "Old_Invoice");
+    }
+}
@@ -0,0 +1,303 @@
+/// Tags whose first argument is a type. `@psalm-` and `@phpstan-` prefixed
+/// variants are treated as the plain tag.
+const TYPE_TAGS: &[&str] = &[
+    "param",
+    "param-out",
+    "return",
+    "var",
+    "throws",
+    "property",
+    "property-read",
+    "property-write",
+    "extends",
+    "implements",
+    "use",
+    "mixin",
+    "template-extends",
+    "template-implements",
+    "template-use",
+];
+
+/// Tags declaring a template parameter, optionally bounded with `of`/`as`
+const TEMPLATE_TAGS: &[&str] = &["template", "template-covariant", "template-contravariant"];
+
+const TOOL_PREFIXES: &[&str] = &["psalm-", "phpstan-", "phan-"];
+
+/// Keywords and pseudo-types that are not class names. Hyphenated pseudo-types
+/// such as `class-string` or `non-empty-list` are recognised by their hyphen.
+const PSEUDO_TYPES: &[&str] = &[
+    "int", "integer", "float", "double", "string", "bool", "boolean", "array", "object",
+    "callable", "iterable", "void", "mixed", "never", "noreturn", "true", "false", "null",
+    "self", "parent", "static", "resource", "list", "numeric", "scalar", "empty", "number",
+];
+
+/// A class name written in a docblock type, with its byte offset in the comment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocblockType {
+    pub name: String,
+    pub offset: usize,
+}
+
+/// A `@var` tag whose type is a single class, such as `@var ?Foo $foo`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocblockVariable {
+    pub class: String,
+    /// The annotated variable, including its `$`, when the tag names one
+    pub variable: Option<String>,
+}
+
+/// Class names and template parameters found in one docblock
+#[derive(Debug, Default)]
+pub struct Docblock {
+    pub types: Vec<DocblockType>,
+    /// Names declared with `@template`, which shadow class names in the types
+    pub templates: Vec<String>,
+    /// `@var` tags naming a single class, used to infer variable and property types
+    pub variables: Vec<DocblockVariable>,
+}
+
+impl Docblock {
+    /// Scan the tags of a `/** ... */` comment for the class names in their types,
+    /// including generic arguments, array shape values and template bounds. Names
+    /// are returned as written; resolving them is left to the caller.
+    pub fn parse(comment: &str) -> Self {
+        let mut docblock = Self::default();
+        let mut line_start = 0;
+
+        for line in comment.split_inclusive('\n') {
+            let start = line_start;
+            line_start += line.len();
+
+            let content = line.trim_start();
+            let content = content.strip_prefix("/**").unwrap_or(content).trim_start();
+            let content = content.strip_prefix('*').unwrap_or(content).trim_start();
+            let Some(tag_line) = content.strip_prefix('@') else {
+                continue;
+            };
+
+            let tag_len = tag_line
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-'))
+                .unwrap_or(tag_line.len());
+            let tag = &tag_line[..tag_len];
+            let tag = TOOL_PREFIXES
+                .iter()
+                .find_map(|prefix| tag.strip_prefix(prefix))
+                .unwrap_or(tag);
+            let rest = &tag_line[tag_len..];
+            let rest_offset = start + line.len() - rest.len();
+
+            if TEMPLATE_TAGS.contains(&tag) {
+                docblock.parse_template(rest, rest_offset);
+            } else if TYPE_TAGS.contains(&tag) {
+                let (expression, offset) = type_expression(rest);
+                collect_types(expression, rest_offset + offset, &mut docblock.types);
+                if tag == "var" {
+                    docblock.parse_variable(expression, &rest[offset + expression.len()..]);
+                }
+            }
+        }
+
+        let templates = &docblock.templates;
+        docblock.types.retain(|t| !templates.contains(&t.name));
+        docblock.variables.retain(|v| !templates.contains(&v.class));
+        docblock
+    }
+
+    /// `@var Foo $foo`, when the type is a single class, optionally nullable
+    fn parse_variable(&mut self, expression: &str, after: &str) {
+        let expression = expression.strip_prefix('?').unwrap_or(expression);
+        let mut classes = expression.split('|').filter(|part| !part.eq_ignore_ascii_case("null"));
+        let (Some(class), None) = (classes.next(), classes.next()) else {
+            return;
+        };
+        let starts_like_name = class.starts_with(|c: char| c.is_alphabetic() || c == '_' || c == '\\');
+        if !starts_like_name || word_len(class) != class.len() || !is_class_name(class) {
+            return;
+        }
+
+        let variable = after
+            .trim_start()
+            .strip_prefix('$')
+            .map(|name| format!("${}", &name[..word_len(name)]))
+            .filter(|name| name.len() > 1);
+        self.variables.push(DocblockVariable {
+            class: class.to_string(),
+            variable,
+        });
+    }
+
+    /// `@template T of Bound`
+    fn parse_template(&mut self, rest: &str, rest_offset: usize) {
+        let name = rest.trim_start();
+        let name_len = word_len(name);
+        if name_len == 0 {
+            return;
+        }
+        self.templates.push(name[..name_len].to_string());
+
+        let after_name = &name[name_len..];
+        let bound = after_name.trim_start();
+        let Some(bound) = bound.strip_prefix("of ").or_else(|| bound.strip_prefix("as ")) else {
+            return;
+        };
+        let (expression, offset) = type_expression(bound);
+        collect_types(expression, rest_offset + rest.len() - bound.len() + offset, &mut self.types);
+    }
+}
+
+/// The type at the start of a tag's arguments and its offset within them. A type
+/// ends at whitespace outside brackets, except around `|`, `&` and the `:` of a
+/// callable return type.
+fn type_expression(rest: &str) -> (&str, usize) {
+    let start = rest.len() - rest.trim_start_matches([' ', '\t']).len();
+    let mut depth = 0usize;
+    let mut end = rest.len();
+    let mut previous = ' ';
+
+    for (i, c) in rest[start..].char_indices() {
+        match c {
+            '<' | '(' | '{' | '[' => depth += 1,
+            '>' | ')' | '}' | ']' => depth = depth.saturating_sub(1),
+            c if c.is_whitespace() && depth == 0 => {
+                let next = rest[start + i..].trim_start_matches([' ', '\t']).chars().next();
+                let continues = matches!(previous, '|' | '&' | ':') || matches!(next, Some('|' | '&'));
+                if c == '\n' || !continues {
+                    end = start + i;
+                    break;
+                }
+            }
+            _ => {}
+        }
+        if !c.is_whitespace() {
+            previous = c;
+        }
+    }
+
+    (&rest[start..end], start)
+}
+
+/// Push every class name in a type expression, skipping pseudo-types, literals,
+/// variable names, array shape keys and the constant part of `Foo::BAR`
+fn collect_types(expression: &str, base: usize, types: &mut Vec<DocblockType>) {
+    let mut i = 0;
+    while let Some(c) = expression[i..].chars().next() {
+        if c == '\'' || c == '"' {
+            i = expression[i + 1..]
+                .find(c)
+                .map_or(expression.len(), |end| i + end + 2);
+        } else if c == '$' || c.is_ascii_digit() {
+            i += 1 + word_len(&expression[i + 1..]);
+        } else if c.is_alphabetic() || c == '_' || c == '\\' {
+            let len = word_len(&expression[i..]);
+            let name = &expression[i..i + len];
+            let after = expression[i + len..].trim_start();
+            let is_shape_key = (after.starts_with(':') && !after.starts_with("::")) || after.starts_with("?:");
+            let is_constant = expression[..i].ends_with("::");
+            if !is_shape_key && !is_constant && is_class_name(name) {
+                types.push(DocblockType {
+                    name: name.to_string(),
+                    offset: base + i,
+                });
+            }
+            i += len;
+            // The bounds of `int<min, max>` are numbers or `min`/`max`, never classes
+            if name.eq_ignore_ascii_case("int") && expression[i..].starts_with('<') {
+                i = expression[i..].find('>').map_or(expression.len(), |end| i + end + 1);
+            }
+        } else {
+            i += c.len_utf8();
+        }
+    }
+}
+
+fn word_len(s: &str) -> usize {
+    s.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '\\' || c == '-'))
+        .unwrap_or(s.len())
+}
+
+fn is_class_name(name: &str) -> bool {
+    !name.contains('-') && !PSEUDO_TYPES.contains(&name.to_lowercase().as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(comment: &str) -> Vec<String> {
+        Docblock::parse(comment).types.into_iter().map(|t| t.name).collect()
+    }
+
+    #[test]
+    fn test_extracts_tag_types() {
+        let comment = r#"/**
+         * Does things.
+         *
+         * @param UserRepository|null $repo The repository
+         * @param array<int, Order> $orders
+         * @param array{user: User, total?: Money, 'note': string} $shape
+         * @param callable(Event): Response $handler
+         * @return Collection<int, \Shop\Order>
+         * @throws PaymentException when it fails
+         * @psalm-param class-string<Handler> $class
+         * @var non-empty-list<Status::*>
+         * @see Unrelated
+         */"#;
+
+        assert_eq!(names(comment), vec![
+            "UserRepository",
+            "Order",
+            "User",
+            "Money",
+            "Event",
+            "Response",
+            "Collection",
+            "\\Shop\\Order",
+            "PaymentException",
+            "Handler",
+            "Status",
+        ]);
+    }
+
+    #[test]
+    fn test_int_range_bounds_are_not_classes() {
+        assert_eq!(names("/** @param int<min, 0>|Max $x */"), vec!["Max"]);
+        assert_eq!(names("/** @return int<1, max> */"), Vec::<String>::new());
+        assert_eq!(names("/** @param min $x */"), vec!["min"]);
+    }
+
+    #[test]
+    fn test_templates_shadow_class_names() {
+        let comment = "/**\n * @template TKey of array-key\n * @template TModel of Model\n * @extends Collection<TKey, TModel>\n * @implements Arrayable<TKey, Item>\n */";
+        let docblock = Docblock::parse(comment);
+
+        assert_eq!(docblock.templates, vec!["TKey", "TModel"]);
+        let names: Vec<_> = docblock.types.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["Model", "Collection", "Arrayable", "Item"]);
+
+        // Offsets point at the name within the comment
+        let model = &docblock.types[0];
+        assert_eq!(&comment[model.offset..model.offset + 5], "Model");
+    }
+
+    #[test]
+    fn test_single_line_var() {
+        assert_eq!(names("/** @var Foo $foo */"), vec!["Foo"]);
+        assert_eq!(names("/** @var Foo*/"), vec!["Foo"]);
+    }
+
+    #[test]
+    fn test_var_tags_name_variable_classes() {
+        let variables = |comment: &str| {
+            Docblock::parse(comment)
+                .variables
+                .into_iter()
+                .map(|v| (v.class, v.variable))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(variables("/** @var ?Mailer $mailer */"), vec![("Mailer".to_string(), Some("$mailer".to_string()))]);
+        assert_eq!(variables("/** @psalm-var \\App\\Clock|null */"), vec![("\\App\\Clock".to_string(), None)]);
+        assert!(variables("/** @var Foo|Bar $x */").is_empty());
+        assert!(variables("/** @var array<int, Foo> $x */").is_empty());
+        assert!(variables("/** @var string $x */").is_empty());
+    }
+}
@@ -0,0 +1,2280 @@
+use crate::analyzer::docblock::{Docblock, DocblockVariable};
+use crate::analyzer::php_parser::{line_and_column, parse_php_file};
+use crate::model::{
+    ClassAlias, Diagnostic, DiagnosticKind, FileModel, Import, ImportKind, Include, IncludeKind, Location, MethodCall, Reference, ReferenceKind, Symbol,
+    SymbolKind, TRAIT_PARENT,
+};
+use bumpalo::Bump;
+use mago_database::file::File;
+use mago_span::HasSpan;
+use mago_syntax::comments::docblock::get_docblock_before_position;
+use mago_syntax::ast::*;
+use std::collections::HashMap;
+
+/// Tracks `use` imports in the current namespace scope, with separate tables
+/// for classes, functions and constants as PHP keeps them apart
+#[derive(Debug, Default, Clone)]
+struct ImportContext {
+    /// Map of short name -> fully qualified class name
+    classes: HashMap<String, String>,
+    /// Map of short name -> fully qualified function name (`use function`)
+    functions: HashMap<String, String>,
+    /// Map of short name -> fully qualified constant name (`use const`)
+    constants: HashMap<String, String>,
+}
+
+impl ImportContext {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn table(&self, kind: ImportKind) -> &HashMap<String, String> {
+        match kind {
+            ImportKind::Class => &self.classes,
+            ImportKind::Function => &self.functions,
+            ImportKind::Const => &self.constants,
+        }
+    }
+
+    fn table_mut(&mut self, kind: ImportKind) -> &mut HashMap<String, String> {
+        match kind {
+            ImportKind::Class => &mut self.classes,
+            ImportKind::Function => &mut self.functions,
+            ImportKind::Const => &mut self.constants,
+        }
+    }
+
+    fn add_import(&mut self, kind: ImportKind, fully_qualified: String, alias: Option<String>) -> String {
+        let short_name = if let Some(alias) = alias {
+            alias
+        } else {
+            // Extract the last part of the FQN as the short name
+            fully_qualified
+                .split('\\')
+                .next_back()
+                .unwrap_or(&fully_qualified)
+                .to_string()
+        };
+        self.table_mut(kind).insert(short_name.clone(), fully_qualified);
+        short_name
+    }
+
+    fn resolve(&self, kind: ImportKind, name: &str) -> Option<&String> {
+        self.table(kind).get(name)
+    }
+}
+
+/// Parse a PHP file and extract its symbols, references, imports, includes and parse diagnostics
+pub fn extract_file(source: &File) -> FileModel {
+    let arena = Bump::new();
+    let (program, diagnostics) = parse_php_file(&arena, source);
+
+    let mut extractor = Extractor::new(source, program.trivia.as_slice());
+    extractor.file.diagnostics = diagnostics;
+    extractor.visit_program(program);
+    extractor.file
+}
+
+/// Lexical parent directory of a path, as `dirname()` computes it. Paths ending in
+/// `.` or `..` get another `..` so walking up from a relative path keeps going.
+fn parent_path(path: &str) -> String {
+    let trimmed = path.trim_end_matches('/');
+    if trimmed.is_empty() {
+        return "/".to_string();
+    }
+    match trimmed.rsplit_once('/') {
+        _ if trimmed == "." || trimmed == ".." || trimmed.ends_with("/..") => format!("{}/..", trimmed),
+        Some(("", _)) => "/".to_string(),
+        Some((parent, _)) => parent.to_string(),
+        None => ".".to_string(),
+    }
+}
+
+/// Whether a name is a valid PHP identifier
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c == '_' || c.is_alphabetic()) && chars.all(|c| c == '_' || c.is_alphanumeric())
+}
+
+/// The class a string literal names when it is written like a class name
+/// (`'App\Models\User'`) or a static callable (`'App\Models\User::find'`).
+/// Strings are always fully qualified. Only names holding a namespace separator
+/// or starting with an uppercase letter count, so plain words are not taken
+/// for classes.
+fn string_class_name(value: &str) -> Option<&str> {
+    let value = value.strip_prefix('\\').unwrap_or(value);
+    let class = match value.split_once("::") {
+        Some((class, method)) if is_identifier(method) => class,
+        Some(_) => return None,
+        None => value,
+    };
+    let looks_like_class = class.contains('\\') || class.starts_with(|c: char| c.is_uppercase());
+    (looks_like_class && class.split('\\').all(is_identifier)).then_some(class)
+}
+
+/// Name of a function symbol: its FQN with `()` appended, so a function never
+/// shares a graph node with a class of the same name
+pub fn function_symbol_name(fqn: &str) -> String {
+    format!("{}()", fqn)
+}
+
+/// Name of a method in the method call graph, e.g. `App\Repository::save`
+pub fn method_symbol_name(class: &str, method: &str) -> String {
+    format!("{}::{}", class, method)
+}
+
+/// Single pass over a file's AST that records declared class-likes and every class
+/// they reference, resolved against the namespace and `use` imports in scope
+struct Extractor<'f> {
+    file: FileModel,
+    /// Source file, used to turn span offsets into lines and columns
+    source: &'f File,
+    /// Comments of the file, searched for the docblock preceding a declaration
+    trivia: &'f [Trivia<'f>],
+    namespace: Option<String>,
+    imports: ImportContext,
+    /// Symbol whose body is being visited: a class-like, a function, or the
+    /// file's script for top-level code
+    current_symbol: Option<String>,
+    /// Resolved parent of the class being visited, the target of `parent`
+    current_parent: Option<String>,
+    /// Whether the class-like being visited is a trait, where `self`, `static`
+    /// and `parent` depend on the class using it
+    in_trait: bool,
+    /// Name of the method whose signature or body is being visited
+    current_method: Option<String>,
+    /// `@template` parameters in scope, which are not class names
+    templates: Vec<String>,
+    /// Classes of local variables known from parameter hints and `new`
+    /// assignments, keyed by variable name, for resolving method call receivers
+    local_types: HashMap<String, String>,
+    /// Classes of the typed properties of the class being visited
+    property_types: HashMap<String, String>,
+    /// Whether an anonymous class body is being visited, where `$this` is not
+    /// the enclosing class
+    in_anonymous_class: bool,
+}
+
+impl<'f> Extractor<'f> {
+    fn new(source: &'f File, trivia: &'f [Trivia<'f>]) -> Self {
+        Self {
+            file: FileModel::new(source.name.to_string()),
+            source,
+            trivia,
+            namespace: None,
+            imports: ImportContext::new(),
+            current_symbol: None,
+            current_parent: None,
+            in_trait: false,
+            current_method: None,
+            templates: Vec::new(),
+            local_types: HashMap::new(),
+            property_types: HashMap::new(),
+            in_anonymous_class: false,
+        }
+    }
+
+    fn visit_program(&mut self, program: &Program) {
+        for statement in program.statements.iter() {
+            self.visit_statement(statement);
+        }
+    }
+
+    fn visit_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Use(use_stmt) => {
+                self.process_use_statement(use_stmt);
+            }
+            Statement::Namespace(ns) => {
+                // Namespace gets its own import context
+                self.namespace = ns.name.as_ref().map(|name| name.value().to_string());
+                self.imports = ImportContext::new();
+
+                for stmt in ns.statements().iter() {
+                    self.visit_statement(stmt);
+                }
+            }
+            Statement::Class(class) => {
+                self.process_class(class);
+            }
+            Statement::Interface(interface) => {
+                self.process_interface(interface);
+            }
+            Statement::Trait(trait_def) => {
+                self.process_trait(trait_def);
+            }
+            Statement::Enum(enum_def) => {
+                self.process_enum(enum_def);
+            }
+            Statement::Function(function) => {
+                self.process_function(function);
+            }
+            Statement::OpeningTag(_)
+            | Statement::ClosingTag(_)
+            | Statement::Inline(_)
+            | Statement::Declare(_)
+            | Statement::HaltCompiler(_)
+            | Statement::Noop(_) => {}
+            // Anything else is top-level code
+            other => self.visit_script_statement(other),
+        }
+    }
+
+    /// Walk a top-level statement as part of the file's script, a symbol named
+    /// after the file that is declared on its first top-level statement
+    fn visit_script_statement(&mut self, statement: &Statement) {
+        let script = self.file.path.clone();
+        if !self.file.symbols.iter().any(|s| s.kind == SymbolKind::Script) {
+            self.push_symbol(script.clone(), SymbolKind::Script, statement.span().start.offset);
+        }
+
+        self.current_symbol = Some(script);
+        self.walk_docblock(statement);
+        self.walk_statement(statement);
+        self.current_symbol = None;
+    }
+
+    /// Register a function and walk it as the current symbol. Functions declared
+    /// inside other code, such as `if (!function_exists(...))` guards, are
+    /// declarations too; the enclosing scope is restored afterwards.
+    fn process_function(&mut self, function: &Function) {
+        let fqn = function_symbol_name(&self.get_fqn(function.name.value));
+        self.push_symbol(fqn.clone(), SymbolKind::Function, function.name.span.start.offset);
+
+        let outer_symbol = self.current_symbol.replace(fqn);
+        let outer_method = self.current_method.take();
+        let outer_templates = self.templates.len();
+        let outer_locals = std::mem::take(&mut self.local_types);
+
+        self.walk_docblock(function);
+        self.walk_attribute_lists(function.attribute_lists.iter());
+        self.walk_parameter_list(&function.parameter_list);
+        if let Some(ref return_type) = function.return_type_hint {
+            self.walk_hint(&return_type.hint, ReferenceKind::ReturnHint);
+        }
+        self.walk_block(&function.body);
+
+        self.current_symbol = outer_symbol;
+        self.current_method = outer_method;
+        self.templates.truncate(outer_templates);
+        self.local_types = outer_locals;
+    }
+
+    /// Declare a class-like found inside other code, then restore the enclosing scope
+    fn process_nested_class_like(&mut self, statement: &Statement) {
+        let outer_symbol = self.current_symbol.take();
+        let outer_parent = self.current_parent.take();
+        let outer_in_trait = self.in_trait;
+        let outer_method = self.current_method.take();
+        let outer_templates = std::mem::take(&mut self.templates);
+        let outer_locals = std::mem::take(&mut self.local_types);
+        let outer_properties = std::mem::take(&mut self.property_types);
+        let outer_anonymous = std::mem::replace(&mut self.in_anonymous_class, false);
+
+        self.visit_statement(statement);
+
+        self.current_symbol = outer_symbol;
+        self.current_parent = outer_parent;
+        self.in_trait = outer_in_trait;
+        self.current_method = outer_method;
+        self.templates = outer_templates;
+        self.local_types = outer_locals;
+        self.property_types = outer_properties;
+        self.in_anonymous_class = outer_anonymous;
+    }
+
+    fn process_use_statement(&mut self, use_stmt: &Use) {
+        match &use_stmt.items {
+            // use A\B, C\D as E;
+            UseItems::Sequence(seq) => {
+                for item in seq.items.iter() {
+                    self.add_use_item(ImportKind::Class, None, item);
+                }
+            }
+            // use function A\b, C\d;
+            UseItems::TypedSequence(seq) => {
+                let kind = Self::import_kind(&seq.r#type);
+                for item in seq.items.iter() {
+                    self.add_use_item(kind, None, item);
+                }
+            }
+            // use function A\{b, c};
+            UseItems::TypedList(list) => {
+                let kind = Self::import_kind(&list.r#type);
+                for item in list.items.iter() {
+                    self.add_use_item(kind, Some(list.namespace.value()), item);
+                }
+            }
+            // use A\{B, function c, const D};
+            UseItems::MixedList(list) => {
+                for typed in list.items.iter() {
+                    let kind = typed
+                        .r#type
+                        .as_ref()
+                        .map(Self::import_kind)
+                        .unwrap_or(ImportKind::Class);
+                    self.add_use_item(kind, Some(list.namespace.value()), &typed.item);
+                }
+            }
+        }
+    }
+
+    fn import_kind(use_type: &UseType) -> ImportKind {
+        match use_type {
+            UseType::Function(_) => ImportKind::Function,
+            UseType::Const(_) => ImportKind::Const,
+        }
+    }
+
+    /// Record one imported name, joined onto the group prefix for `use A\{...}` forms
+    fn add_use_item(&mut self, kind: ImportKind, prefix: Option<&str>, item: &UseItem) {
+        let name = item.name.value().trim_start_matches('\\');
+        let fqn = match prefix {
+            Some(prefix) => format!("{}\\{}", prefix.trim_start_matches('\\').trim_end_matches('\\'), name),
+            None => name.to_string(),
+        };
+        let alias = item.alias.as_ref().map(|a| a.identifier.value.to_string());
+        let alias = self.imports.add_import(kind, fqn.clone(), alias);
+        self.file.imports.push(Import {
+            namespace: self.namespace.clone(),
+            kind,
+            alias,
+            fqn,
+        });
+    }
+
+    /// Declare a symbol whose declaration starts at `offset`
+    fn push_symbol(&mut self, fqn: String, kind: SymbolKind, offset: u32) {
+        let (line, _) = line_and_column(self.source, offset);
+        self.file.symbols.push(Symbol {
+            fqn,
+            kind,
+            namespace: self.namespace.clone(),
+            file: self.file.path.clone(),
+            line,
+            methods: Vec::new(),
+            parents: Vec::new(),
+        });
+    }
+
+    /// Add a method to the class-like being visited
+    fn declare_method(&mut self, name: &str) {
+        let Some(class) = &self.current_symbol else {
+            return;
+        };
+        if let Some(symbol) = self.file.symbols.iter_mut().rev().find(|s| &s.fqn == class) {
+            symbol.methods.push(name.to_string());
+        }
+    }
+
+    /// Add a declared parent to the class-like being visited
+    fn declare_parent(&mut self, parent: String) {
+        let Some(class) = &self.current_symbol else {
+            return;
+        };
+        if let Some(symbol) = self.file.symbols.iter_mut().rev().find(|s| &s.fqn == class) {
+            symbol.parents.push(parent);
+        }
+    }
+
+    /// Remember the classes of typed properties, including promoted constructor
+    /// parameters and properties typed with `@var`, so calls on `$this->property`
+    /// can be resolved in methods declared before the property
+    fn collect_property_types<'s, 'a: 's>(&mut self, members: impl Iterator<Item = &'s ClassLikeMember<'a>>) {
+        for member in members {
+            match member {
+                ClassLikeMember::Property(property) => {
+                    let class = property.hint().and_then(|hint| self.hint_class(hint)).or_else(|| {
+                        let (docblock, _) = self.docblock_before(member)?;
+                        let var = docblock.variables.into_iter().next()?;
+                        Some(self.resolve_class_name(&var.class))
+                    });
+                    let Some(class) = class else {
+                        continue;
+                    };
+                    for variable in property.variables() {
+                        self.property_types
+                            .insert(variable.name.trim_start_matches('$').to_string(), class.clone());
+                    }
+                }
+                ClassLikeMember::Method(method) if method.name.value.eq_ignore_ascii_case("__construct") => {
+                    for param in method.parameter_list.parameters.iter() {
+                        if param.modifiers.is_empty() {
+                            continue;
+                        }
+                        if let Some(class) = param.hint.as_ref().and_then(|hint| self.hint_class(hint)) {
+                            self.property_types
+                                .insert(param.variable.name.trim_start_matches('$').to_string(), class);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Register a class-like declaration and make it the current class
+    fn declare_symbol(&mut self, name: &LocalIdentifier, kind: SymbolKind) {
+        let fqn = self.get_fqn(name.value);
+        self.push_symbol(fqn.clone(), kind, name.span.start.offset);
+        self.current_symbol = Some(fqn);
+        self.current_parent = None;
+        self.in_trait = kind == SymbolKind::Trait;
+        self.templates.clear();
+        self.property_types.clear();
+    }
+
+    fn process_class(&mut self, class: &Class) {
+        self.declare_symbol(&class.name, SymbolKind::Class);
+        self.walk_docblock(class);
+
+        // Analyze attributes (PHP 8+)
+        self.walk_attribute_lists(class.attribute_lists.iter());
+
+        // Analyze parent class
+        if let Some(ref extends) = class.extends {
+            for parent in extends.types.iter() {
+                self.add_reference(parent, ReferenceKind::Extends);
+                let parent = self.resolve_class_name(parent.value());
+                self.declare_parent(parent.clone());
+                self.current_parent = Some(parent);
+            }
+        }
+
+        // Analyze interfaces
+        if let Some(ref implements) = class.implements {
+            for interface in implements.types.iter() {
+                self.add_reference(interface, ReferenceKind::Implements);
+            }
+        }
+
+        // Visit class members
+        self.collect_property_types(class.members.iter());
+        for member in class.members.iter() {
+            self.visit_class_member(member);
+        }
+
+        self.current_symbol = None;
+    }
+
+    fn process_interface(&mut self, interface: &Interface) {
+        self.declare_symbol(&interface.name, SymbolKind::Interface);
+        self.walk_docblock(interface);
+        self.walk_attribute_lists(interface.attribute_lists.iter());
+
+        // Analyze parent interfaces
+        if let Some(ref extends) = interface.extends {
+            for parent in extends.types.iter() {
+                self.add_reference(parent, ReferenceKind::Extends);
+                self.declare_parent(self.resolve_class_name(parent.value()));
+            }
+        }
+
+        // Visit constants and method signatures
+        for member in interface.members.iter() {
+            self.visit_class_member(member);
+        }
+
+        self.current_symbol = None;
+    }
+
+    fn process_trait(&mut self, trait_def: &Trait) {
+        self.declare_symbol(&trait_def.name, SymbolKind::Trait);
+        self.walk_docblock(trait_def);
+        self.walk_attribute_lists(trait_def.attribute_lists.iter());
+
+        // Visit trait members
+        self.collect_property_types(trait_def.members.iter());
+        for member in trait_def.members.iter() {
+            self.visit_class_member(member);
+        }
+
+        self.current_symbol = None;
+    }
+
+    fn process_enum(&mut self, enum_def: &Enum) {
+        self.declare_symbol(&enum_def.name, SymbolKind::Enum);
+        self.walk_docblock(enum_def);
+        self.walk_attribute_lists(enum_def.attribute_lists.iter());
+
+        // Analyze backing type hint
+        if let Some(ref backing) = enum_def.backing_type_hint {
+            self.walk_hint(&backing.hint, ReferenceKind::PropertyHint);
+        }
+
+        // Analyze interfaces
+        if let Some(ref implements) = enum_def.implements {
+            for interface in implements.types.iter() {
+                self.add_reference(interface, ReferenceKind::Implements);
+            }
+        }
+
+        // Visit cases, constants and methods
+        for member in enum_def.members.iter() {
+            self.visit_class_member(member);
+        }
+
+        self.current_symbol = None;
+    }
+
+    fn visit_class_member(&mut self, member: &ClassLikeMember) {
+        // A method reads its docblock once it is the current method, so the
+        // types are attributed to it
+        if !matches!(member, ClassLikeMember::Method(_)) {
+            self.walk_docblock(member);
+        }
+
+        match member {
+            ClassLikeMember::TraitUse(trait_use) => {
+                let kind = if self.in_anonymous_class {
+                    ReferenceKind::Instantiation
+                } else {
+                    ReferenceKind::TraitUse
+                };
+                for trait_name in trait_use.trait_names.iter() {
+                    self.add_reference(trait_name, kind);
+                }
+            }
+            ClassLikeMember::Constant(constant) => {
+                self.walk_attribute_lists(constant.attribute_lists.iter());
+                if let Some(ref hint) = constant.hint {
+                    self.walk_hint(hint, ReferenceKind::PropertyHint);
+                }
+                for item in constant.items.iter() {
+                    self.walk_expression(&item.value);
+                }
+            }
+            ClassLikeMember::EnumCase(case) => {
+                self.walk_attribute_lists(case.attribute_lists.iter());
+                if let EnumCaseItem::Backed(item) = &case.item {
+                    self.walk_expression(&item.value);
+                }
+            }
+            ClassLikeMember::Property(Property::Plain(plain)) => {
+                self.walk_attribute_lists(plain.attribute_lists.iter());
+                if let Some(ref hint) = plain.hint {
+                    self.walk_hint(hint, ReferenceKind::PropertyHint);
+                }
+                for item in plain.items.iter() {
+                    self.walk_property_item(item);
+                }
+            }
+            ClassLikeMember::Property(Property::Hooked(hooked)) => {
+                self.walk_attribute_lists(hooked.attribute_lists.iter());
+                if let Some(ref hint) = hooked.hint {
+                    self.walk_hint(hint, ReferenceKind::PropertyHint);
+                }
+                self.walk_property_item(&hooked.item);
+                for hook in hooked.hook_list.hooks.iter() {
+                    self.walk_attribute_lists(hook.attribute_lists.iter());
+                    if let Some(ref parameter_list) = hook.parameter_list {
+                        self.walk_parameter_list(parameter_list);
+                    }
+                    match &hook.body {
+                        PropertyHookBody::Concrete(PropertyHookConcreteBody::Block(block)) => self.walk_block(block),
+                        PropertyHookBody::Concrete(PropertyHookConcreteBody::Expression(body)) => {
+                            self.walk_expression(&body.expression)
+                        }
+                        PropertyHookBody::Abstract(_) => {}
+                    }
+                }
+            }
+            ClassLikeMember::Method(method) => {
+                // An anonymous class's methods are part of the enclosing method
+                let outer_method = self.current_method.clone();
+                if !self.in_anonymous_class {
+                    self.declare_method(method.name.value);
+                    self.current_method = Some(method.name.value.to_string());
+                }
+                let class_templates = self.templates.len();
+                let outer_locals = std::mem::take(&mut self.local_types);
+                self.walk_docblock(method);
+                self.walk_attribute_lists(method.attribute_lists.iter());
+
+                // Check return type
+                if let Some(ref return_type) = method.return_type_hint {
+                    self.walk_hint(&return_type.hint, ReferenceKind::ReturnHint);
+                }
+
+                // Check parameters, including attributes on promoted constructor properties
+                self.walk_parameter_list(&method.parameter_list);
+
+                // Check method body for usage-based dependencies
+                if let MethodBody::Concrete(block) = &method.body {
+                    self.walk_block(block);
+                }
+
+                self.current_method = outer_method;
+                self.templates.truncate(class_templates);
+                self.local_types = outer_locals;
+            }
+        }
+    }
+
+    /// Name relative to the current namespace. A `namespace\` prefix names the
+    /// current namespace explicitly.
+    fn get_fqn(&self, name: &str) -> String {
+        let name = match name.split_once('\\') {
+            Some((keyword, rest)) if keyword.eq_ignore_ascii_case("namespace") => rest,
+            _ => name,
+        };
+        if let Some(ns) = &self.namespace {
+            format!("{}\\{}", ns, name)
+        } else {
+            name.to_string()
+        }
+    }
+
+    fn resolve_class_name(&self, name: &str) -> String {
+        // Check if it's already fully qualified
+        if let Some(stripped) = name.strip_prefix('\\') {
+            // Remove leading backslash for consistency
+            return stripped.to_string();
+        }
+
+        // Check if there's a use statement import for this name
+        if let Some(fqn) = self.imports.resolve(ImportKind::Class, name) {
+            return fqn.clone();
+        }
+
+        // A qualified name such as `Models\User` resolves its first segment
+        // against the imports, e.g. `use App\Models;`
+        if let Some((first, rest)) = name.split_once('\\') {
+            if let Some(fqn) = self.imports.resolve(ImportKind::Class, first) {
+                return format!("{}\\{}", fqn, rest);
+            }
+        }
+
+        // Otherwise, resolve relative to current namespace
+        self.get_fqn(name)
+    }
+
+    /// Resolve a called function name. An unqualified name in a namespace resolves
+    /// into the namespace; `CodeModel::resolve_function_fallbacks` later points it
+    /// at the global function when the namespace does not define one, as PHP does.
+    fn resolve_function_name(&self, name: &str) -> String {
+        if let Some(stripped) = name.strip_prefix('\\') {
+            return function_symbol_name(stripped);
+        }
+        if let Some(fqn) = self.imports.resolve(ImportKind::Function, name) {
+            return function_symbol_name(fqn);
+        }
+        if let Some((first, rest)) = name.split_once('\\') {
+            if let Some(fqn) = self.imports.resolve(ImportKind::Class, first) {
+                return function_symbol_name(&format!("{}\\{}", fqn, rest));
+            }
+        }
+        function_symbol_name(&self.get_fqn(name))
+    }
+
+    fn is_class_type(&self, type_name: &str) -> bool {
+        // Filter out built-in types
+        !matches!(
+            type_name.to_lowercase().as_str(),
+            "int" | "float" | "string" | "bool" | "array" | "object"
+            | "callable" | "iterable" | "void" | "mixed" | "never"
+            | "true" | "false" | "null" | "self" | "parent" | "static"
+        )
+    }
+
+    /// 1-based file/line/column of a byte offset in the file
+    fn location(&self, offset: u32) -> Location {
+        let (line, column) = line_and_column(self.source, offset);
+        Location {
+            file: self.file.path.clone(),
+            line,
+            column,
+        }
+    }
+
+    /// Resolve a class name as written in the source and record it as a
+    /// dependency of the current class
+    fn add_reference(&mut self, identifier: &Identifier, kind: ReferenceKind) {
+        self.add_named_reference(identifier.value(), identifier.span().start.offset, kind);
+    }
+
+    /// Record a class name found at a byte offset, for names that are not
+    /// identifiers in the AST such as docblock types
+    fn add_named_reference(&mut self, name: &str, offset: u32, kind: ReferenceKind) {
+        let Some(from) = self.current_symbol.clone() else {
+            return;
+        };
+        let to = match name.to_ascii_lowercase().as_str() {
+            "self" | "static" => match self.current_class() {
+                Some(class) => class,
+                None => return,
+            },
+            // Inside a trait, `parent` is the parent of each class using it
+            "parent" if self.in_trait => TRAIT_PARENT.to_string(),
+            "parent" => match &self.current_parent {
+                Some(parent) => parent.clone(),
+                None => return,
+            },
+            _ if self.is_class_type(name) => self.resolve_class_name(name),
+            _ => return,
+        };
+        let location = self.location(offset);
+        self.file.references.push(Reference {
+            from,
+            to,
+            kind,
+            method: self.current_method.clone(),
+            location,
+            unqualified: false,
+        });
+    }
+
+    /// Record an `include`/`require` of another file and walk its path expression
+    fn add_include(&mut self, kind: IncludeKind, construct: &Expression, value: &Expression) {
+        let span = construct.span();
+        let expression = &self.source.contents[span.start.offset as usize..span.end.offset as usize];
+        self.file.includes.push(Include {
+            kind,
+            path: self.static_path(value),
+            expression: expression.to_string(),
+            resolved: None,
+            location: self.location(span.start.offset),
+        });
+        self.walk_expression(value);
+    }
+
+    /// Evaluate an include path built from string literals, `__DIR__`, `__FILE__`
+    /// and `dirname()`. The include_path is not known, so a relative path is taken
+    /// relative to the including file, which PHP also falls back to.
+    fn static_path(&self, expression: &Expression) -> Option<String> {
+        let (path, anchored) = self.static_string(expression)?;
+        if anchored || path.starts_with('/') {
+            Some(path)
+        } else {
+            Some(format!("{}/{}", parent_path(&self.file.path), path))
+        }
+    }
+
+    /// The value of a path expression, and whether it starts from the location
+    /// of this file rather than from a literal
+    fn static_string(&self, expression: &Expression) -> Option<(String, bool)> {
+        match expression {
+            Expression::Literal(Literal::String(string)) => Some((string.value?.to_string(), false)),
+            Expression::MagicConstant(MagicConstant::Directory(_)) => Some((parent_path(&self.file.path), true)),
+            Expression::MagicConstant(MagicConstant::File(_)) => Some((self.file.path.clone(), true)),
+            Expression::Parenthesized(parenthesized) => self.static_string(parenthesized.expression),
+            Expression::Binary(binary) if matches!(binary.operator, BinaryOperator::StringConcat(_)) => {
+                let (lhs, anchored) = self.static_string(binary.lhs)?;
+                let (rhs, _) = self.static_string(binary.rhs)?;
+                Some((lhs + &rhs, anchored))
+            }
+            // dirname(__FILE__), dirname(__DIR__, 2)
+            Expression::Call(Call::Function(call)) => {
+                let Expression::Identifier(function) = call.function else {
+                    return None;
+                };
+                if !function.value().trim_start_matches('\\').eq_ignore_ascii_case("dirname") {
+                    return None;
+                }
+                let mut arguments = call.argument_list.arguments.iter().map(|argument| match argument {
+                    Argument::Positional(argument) => Some(&argument.value),
+                    Argument::Named(_) => None,
+                });
+                let (mut path, anchored) = self.static_string(arguments.next()??)?;
+                let levels = match arguments.next() {
+                    Some(Some(Expression::Literal(Literal::Integer(levels)))) => levels.value?,
+                    Some(_) => return None,
+                    None => 1,
+                };
+                for _ in 0..levels {
+                    path = parent_path(&path);
+                }
+                Some((path, anchored))
+            }
+            _ => None,
+        }
+    }
+
+    /// Record a call to a named function as a dependency of the current symbol
+    fn add_function_call(&mut self, identifier: &Identifier) {
+        let Some(from) = self.current_symbol.clone() else {
+            return;
+        };
+        let name = identifier.value();
+        let to = self.resolve_function_name(name);
+        let unqualified = !name.contains('\\') && self.imports.resolve(ImportKind::Function, name).is_none();
+        let location = self.location(identifier.span().start.offset);
+        self.file.references.push(Reference {
+            from,
+            to,
+            kind: ReferenceKind::FunctionCall,
+            method: self.current_method.clone(),
+            location,
+            unqualified,
+        });
+    }
+
+    /// Record a call to a named method of a known class as a method call graph edge
+    /// from the current method, or from the current function or script
+    fn add_method_call(&mut self, class: Option<String>, method: &ClassLikeMemberSelector) {
+        let (Some(class), ClassLikeMemberSelector::Identifier(method)) = (class, method) else {
+            return;
+        };
+        self.add_call_edge(&class, method.value, method.span.start.offset);
+    }
+
+    fn add_call_edge(&mut self, class: &str, method: &str, offset: u32) {
+        let Some(symbol) = &self.current_symbol else {
+            return;
+        };
+        let from = match &self.current_method {
+            Some(current) => method_symbol_name(symbol, current),
+            None => symbol.clone(),
+        };
+        let location = self.location(offset);
+        self.file.calls.push(MethodCall {
+            from,
+            to: method_symbol_name(class, method),
+            location,
+        });
+    }
+
+    /// Record a string literal written like a class name or a `Class::method`
+    /// callable. It is kept once the analyzed classes are known.
+    fn add_class_string(&mut self, string: &LiteralString) {
+        let (Some(from), Some(class)) = (&self.current_symbol, string.value.and_then(string_class_name)) else {
+            return;
+        };
+        // Past the opening quote
+        let location = self.location(string.span.start.offset + 1);
+        self.file.class_strings.push(Reference {
+            from: from.clone(),
+            to: class.to_string(),
+            kind: ReferenceKind::Dynamic,
+            method: self.current_method.clone(),
+            location,
+            unqualified: false,
+        });
+    }
+
+    /// Record `class_alias(Original::class, 'Alias')` when both names are static.
+    /// Returns whether it was recorded.
+    fn add_class_alias(&mut self, call: &Expression, argument_list: &ArgumentList) -> bool {
+        let mut arguments = argument_list.arguments.iter().map(|argument| match argument {
+            Argument::Positional(argument) => self.class_name_argument(&argument.value),
+            Argument::Named(_) => None,
+        });
+        let (Some(Some(class)), Some(Some(alias))) = (arguments.next(), arguments.next()) else {
+            return false;
+        };
+        let location = self.location(call.span().start.offset);
+        self.file.aliases.push(ClassAlias { alias, class, location });
+        true
+    }
+
+    /// A class named by `Foo::class` or by a string, which is always fully qualified
+    fn class_name_argument(&self, argument: &Expression) -> Option<String> {
+        match argument {
+            Expression::Literal(Literal::String(string)) => {
+                let name = string.value?.trim_start_matches('\\');
+                name.split('\\').all(is_identifier).then(|| name.to_string())
+            }
+            Expression::Access(Access::ClassConstant(access)) => match &access.constant {
+                ClassLikeConstantSelector::Identifier(constant) if constant.value.eq_ignore_ascii_case("class") => {
+                    self.class_expression_name(access.class)
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// `[Foo::class, 'handle']` is a callable, and so a call to `Foo::handle`
+    fn add_callable_array<'s, 'a: 's>(&mut self, elements: impl Iterator<Item = &'s ArrayElement<'a>>) {
+        let elements: Vec<_> = elements.collect();
+        let [ArrayElement::Value(class), ArrayElement::Value(method)] = elements.as_slice() else {
+            return;
+        };
+        let (
+            Expression::Access(Access::ClassConstant(class)),
+            Expression::Literal(Literal::String(LiteralString { value: Some(method), span, .. })),
+        ) = (class.value, method.value)
+        else {
+            return;
+        };
+        let is_class_name = matches!(&class.constant, ClassLikeConstantSelector::Identifier(constant)
+            if constant.value.eq_ignore_ascii_case("class"));
+        if !is_class_name || !is_identifier(method) {
+            return;
+        }
+        if let Some(class) = self.class_expression_name(class.class) {
+            self.add_call_edge(&class, method, span.start.offset + 1);
+        }
+    }
+
+    /// Report `new $class` or `$class::method()`, whose class is only known at
+    /// runtime, so the dependency is missing from the graph. `site` runs from
+    /// the start of the construct to the end of `class`.
+    fn add_dynamic_class_site(&mut self, class: &Expression, start: u32, end: u32) {
+        let is_known = match class {
+            Expression::Identifier(_) | Expression::Self_(_) | Expression::Static(_) | Expression::Parent(_) => true,
+            other => self.static_receiver_class(other).is_some(),
+        };
+        if is_known {
+            return;
+        }
+        let site = &self.source.contents[start as usize..end as usize];
+        let location = self.location(start);
+        self.file.diagnostics.push(Diagnostic {
+            kind: DiagnosticKind::DynamicClass,
+            location,
+            message: format!("`{}` names its class at runtime", site),
+        });
+    }
+
+    /// Record a call on an object of known class: an edge of the method call graph,
+    /// and a dependency of the current symbol on the receiver's class
+    fn add_instance_call(&mut self, object: &Expression, method: &ClassLikeMemberSelector) {
+        let receiver = self.receiver_class(object);
+        if let (Some(class), Some(from)) = (&receiver, &self.current_symbol) {
+            // Calls on `$this` are not a dependency on another class
+            if class != from {
+                let location = self.location(method.span().start.offset);
+                self.file.references.push(Reference {
+                    from: from.clone(),
+                    to: class.clone(),
+                    kind: ReferenceKind::MethodCall,
+                    method: self.current_method.clone(),
+                    location,
+                    unqualified: false,
+                });
+            }
+        }
+        self.add_method_call(receiver, method);
+    }
+
+    /// The class-like being visited, which `self` and `static` refer to. In a
+    /// function or top-level code they name no class.
+    fn current_class(&self) -> Option<String> {
+        let symbol = self.current_symbol.as_ref()?;
+        self.file
+            .symbols
+            .iter()
+            .rev()
+            .find(|s| &s.fqn == symbol)
+            .filter(|s| s.kind.is_class_like())
+            .map(|s| s.fqn.clone())
+    }
+
+    /// The class named on the left-hand side of `new` or `::`, if it is static
+    fn class_expression_name(&self, class: &Expression) -> Option<String> {
+        match class {
+            Expression::Identifier(id) if self.is_class_type(id.value()) => Some(self.resolve_class_name(id.value())),
+            Expression::Self_(_) | Expression::Static(_) => self.current_class(),
+            Expression::Parent(_) if !self.in_trait => self.current_parent.clone(),
+            _ => None,
+        }
+    }
+
+    /// The class a static call goes to: a class name, or `$object::` on an object
+    /// of known class
+    fn static_receiver_class(&self, class: &Expression) -> Option<String> {
+        self.class_expression_name(class).or_else(|| self.receiver_class(class))
+    }
+
+    /// The class named by a type hint, if it names exactly one. `?Foo` and
+    /// `Foo|null` count as `Foo`.
+    fn hint_class(&self, hint: &Hint) -> Option<String> {
+        match hint {
+            Hint::Identifier(id) if self.is_class_type(id.value()) => Some(self.resolve_class_name(id.value())),
+            Hint::Self_(_) | Hint::Static(_) => self.current_class(),
+            Hint::Parent(_) if !self.in_trait => self.current_parent.clone(),
+            Hint::Parenthesized(p) => self.hint_class(p.hint),
+            Hint::Nullable(n) => self.hint_class(n.hint),
+            Hint::Union(u) => match (u.left, u.right) {
+                (Hint::Null(_), other) | (other, Hint::Null(_)) => self.hint_class(other),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The class of the object a method is called on, when it is `$this`, a
+    /// variable or property of known class, or a fresh `new` expression
+    fn receiver_class(&self, object: &Expression) -> Option<String> {
+        match object {
+            Expression::Variable(Variable::Direct(variable)) if variable.name == "$this" => {
+                if self.in_anonymous_class || self.current_method.is_none() {
+                    return None;
+                }
+                self.current_symbol.clone()
+            }
+            Expression::Variable(Variable::Direct(variable)) => self.local_types.get(variable.name).cloned(),
+            Expression::Access(Access::Property(access)) => {
+                let is_this = matches!(access.object, Expression::Variable(Variable::Direct(v)) if v.name == "$this");
+                match &access.property {
+                    ClassLikeMemberSelector::Identifier(property) if is_this && !self.in_anonymous_class => {
+                        self.property_types.get(property.value).cloned()
+                    }
+                    _ => None,
+                }
+            }
+            Expression::Parenthesized(parenthesized) => self.receiver_class(parenthesized.expression),
+            Expression::Instantiation(instantiation) => self.class_expression_name(instantiation.class),
+            _ => None,
+        }
+    }
+
+    /// Record `self`, `static` or `parent`, resolved against the class being visited
+    fn add_keyword_reference(&mut self, keyword: &Keyword, kind: ReferenceKind) {
+        self.add_named_reference(keyword.value, keyword.span.start.offset, kind);
+    }
+
+    /// Record the class referenced by the left-hand side of `new`, `::` or `instanceof`,
+    /// or recurse into it when it is a dynamic expression such as `$class`
+    fn walk_class_expression(&mut self, class: &Expression, kind: ReferenceKind) {
+        match class {
+            Expression::Identifier(id) => self.add_reference(id, kind),
+            Expression::Self_(keyword) | Expression::Static(keyword) | Expression::Parent(keyword) => {
+                self.add_keyword_reference(keyword, kind)
+            }
+            other => self.walk_expression(other),
+        }
+    }
+
+    /// Record the class names in the docblock directly preceding a node. Its
+    /// `@template` parameters stay in scope until the caller drops them.
+    /// Returns its `@var` tags naming a single class.
+    fn walk_docblock(&mut self, node: &impl HasSpan) -> Vec<DocblockVariable> {
+        let Some((docblock, comment_offset)) = self.docblock_before(node) else {
+            return Vec::new();
+        };
+        self.templates.extend(docblock.templates);
+        for doc_type in docblock.types {
+            if self.templates.contains(&doc_type.name) {
+                continue;
+            }
+            let offset = comment_offset + doc_type.offset as u32;
+            self.add_named_reference(&doc_type.name, offset, ReferenceKind::Docblock);
+        }
+        docblock.variables
+    }
+
+    /// The parsed docblock directly preceding a node, with the comment's offset
+    fn docblock_before(&self, node: &impl HasSpan) -> Option<(Docblock, u32)> {
+        let comment = get_docblock_before_position(self.source, self.trivia, node.span().start.offset)?;
+        Some((Docblock::parse(comment.value), comment.span.start.offset))
+    }
+
+    fn walk_block(&mut self, block: &Block) {
+        self.walk_statements(block.statements.iter());
+    }
+
+    fn walk_statements<'s, 'a: 's>(&mut self, statements: impl Iterator<Item = &'s Statement<'a>>) {
+        for statement in statements {
+            // Covers inline `/** @var Foo $foo */` annotations. Functions read
+            // their own docblock.
+            if matches!(statement, Statement::Function(_)) {
+                self.walk_statement(statement);
+                continue;
+            }
+            let annotated: Vec<_> = self
+                .walk_docblock(statement)
+                .into_iter()
+                .filter_map(|var| Some((var.variable?, self.resolve_class_name(&var.class))))
+                .collect();
+            // The annotation holds inside the statement, such as a loop body, and
+            // after it, even though the statement assigns the variable
+            self.local_types.extend(annotated.iter().cloned());
+            self.walk_statement(statement);
+            self.local_types.extend(annotated);
+        }
+    }
+
+    fn walk_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Expression(expr_stmt) => {
+                self.walk_expression(expr_stmt.expression);
+            }
+            Statement::Return(return_stmt) => {
+                if let Some(ref value) = return_stmt.value {
+                    self.walk_expression(value);
+                }
+            }
+            Statement::Block(block) => {
+                self.walk_block(block);
+            }
+            Statement::If(if_stmt) => {
+                self.walk_expression(if_stmt.condition);
+                match &if_stmt.body {
+                    IfBody::Statement(body) => {
+                        self.walk_statement(body.statement);
+                        for clause in body.else_if_clauses.iter() {
+                            self.walk_expression(clause.condition);
+                            self.walk_statement(clause.statement);
+                        }
+                        if let Some(ref else_clause) = body.else_clause {
+                            self.walk_statement(else_clause.statement);
+                        }
+                    }
+                    IfBody::ColonDelimited(body) => {
+                        self.walk_statements(body.statements.iter());
+                        for clause in body.else_if_clauses.iter() {
+                            self.walk_expression(clause.condition);
+                            self.walk_statements(clause.statements.iter());
+                        }
+                        if let Some(ref else_clause) = body.else_clause {
+                            self.walk_statements(else_clause.statements.iter());
+                        }
+                    }
+                }
+            }
+            Statement::Foreach(foreach) => {
+                self.walk_expression(foreach.expression);
+                match &foreach.target {
+                    ForeachTarget::Value(target) => {
+                        self.walk_expression(target.value);
+                    }
+                    ForeachTarget::KeyValue(target) => {
+                        self.walk_expression(target.key);
+                        self.walk_expression(target.value);
+                    }
+                }
+                match &foreach.body {
+                    ForeachBody::Statement(body) => self.walk_statement(body),
+                    ForeachBody::ColonDelimited(body) => self.walk_statements(body.statements.iter()),
+                }
+            }
+            Statement::For(for_stmt) => {
+                for expr in for_stmt.initializations.iter() {
+                    self.walk_expression(expr);
+                }
+                for expr in for_stmt.conditions.iter() {
+                    self.walk_expression(expr);
+                }
+                for expr in for_stmt.increments.iter() {
+                    self.walk_expression(expr);
+                }
+                match &for_stmt.body {
+                    ForBody::Statement(body) => self.walk_statement(body),
+                    ForBody::ColonDelimited(body) => self.walk_statements(body.statements.iter()),
+                }
+            }
+            Statement::While(while_stmt) => {
+                self.walk_expression(while_stmt.condition);
+                match &while_stmt.body {
+                    WhileBody::Statement(body) => self.walk_statement(body),
+                    WhileBody::ColonDelimited(body) => self.walk_statements(body.statements.iter()),
+                }
+            }
+            Statement::DoWhile(do_while) => {
+                self.walk_statement(do_while.statement);
+                self.walk_expression(do_while.condition);
+            }
+            Statement::Switch(switch) => {
+                self.walk_expression(switch.expression);
+                let cases = match &switch.body {
+                    SwitchBody::BraceDelimited(body) => &body.cases,
+                    SwitchBody::ColonDelimited(body) => &body.cases,
+                };
+                for case in cases.iter() {
+                    match case {
+                        SwitchCase::Expression(case) => {
+                            self.walk_expression(case.expression);
+                            self.walk_statements(case.statements.iter());
+                        }
+                        SwitchCase::Default(case) => {
+                            self.walk_statements(case.statements.iter());
+                        }
+                    }
+                }
+            }
+            Statement::Try(try_stmt) => {
+                self.walk_block(&try_stmt.block);
+                for catch_clause in try_stmt.catch_clauses.iter() {
+                    self.walk_hint(&catch_clause.hint, ReferenceKind::Catch);
+                    self.walk_block(&catch_clause.block);
+                }
+                if let Some(ref finally_clause) = try_stmt.finally_clause {
+                    self.walk_block(&finally_clause.block);
+                }
+            }
+            Statement::Echo(echo) => {
+                for value in echo.values.iter() {
+                    self.walk_expression(value);
+                }
+            }
+            Statement::EchoTag(echo) => {
+                for value in echo.values.iter() {
+                    self.walk_expression(value);
+                }
+            }
+            Statement::Unset(unset) => {
+                for value in unset.values.iter() {
+                    self.walk_expression(value);
+                }
+            }
+            Statement::Static(static_stmt) => {
+                for item in static_stmt.items.iter() {
+                    if let StaticItem::Concrete(item) = item {
+                        self.walk_expression(&item.value);
+                    }
+                }
+            }
+            Statement::Constant(constant) => {
+                self.walk_attribute_lists(constant.attribute_lists.iter());
+                for item in constant.items.iter() {
+                    self.walk_expression(&item.value);
+                }
+            }
+            Statement::Declare(declare) => {
+                match &declare.body {
+                    DeclareBody::Statement(body) => self.walk_statement(body),
+                    DeclareBody::ColonDelimited(body) => self.walk_statements(body.statements.iter()),
+                }
+            }
+            Statement::Continue(continue_stmt) => {
+                if let Some(ref level) = continue_stmt.level {
+                    self.walk_expression(level);
+                }
+            }
+            Statement::Break(break_stmt) => {
+                if let Some(ref level) = break_stmt.level {
+                    self.walk_expression(level);
+                }
+            }
+            Statement::Function(function) => {
+                self.process_function(function);
+            }
+            // Class-likes declared inside other code, such as `if (!class_exists(...))`
+            // guards, are declarations in their own right, not references
+            Statement::Class(_) | Statement::Interface(_) | Statement::Trait(_) | Statement::Enum(_) => {
+                self.process_nested_class_like(statement);
+            }
+            Statement::Namespace(_) | Statement::Use(_) => {}
+            // Tags, inline HTML, goto/labels, `global` and no-ops cannot reference classes
+            _ => {}
+        }
+    }
+
+    fn walk_expression(&mut self, expression: &Expression) {
+        match expression {
+            // new ClassName()
+            Expression::Instantiation(instantiation) => {
+                self.add_dynamic_class_site(
+                    instantiation.class,
+                    instantiation.new.span.start.offset,
+                    instantiation.class.span().end.offset,
+                );
+                self.walk_class_expression(instantiation.class, ReferenceKind::Instantiation);
+                if let Some(ref arg_list) = instantiation.argument_list {
+                    self.walk_argument_list(arg_list);
+                }
+            }
+            // Anonymous classes: new class(...) extends Parent implements Interface {}
+            Expression::AnonymousClass(anon_class) => {
+                self.walk_attribute_lists(anon_class.attribute_lists.iter());
+                if let Some(ref arg_list) = anon_class.argument_list {
+                    self.walk_argument_list(arg_list);
+                }
+                // The enclosing class instantiates the anonymous class's parent,
+                // interfaces and traits, but does not inherit from them
+                if let Some(ref extends) = anon_class.extends {
+                    for parent in extends.types.iter() {
+                        self.add_reference(parent, ReferenceKind::Instantiation);
+                    }
+                }
+                if let Some(ref implements) = anon_class.implements {
+                    for interface in implements.types.iter() {
+                        self.add_reference(interface, ReferenceKind::Instantiation);
+                    }
+                }
+
+                // The body is attributed to the enclosing class, with `parent`
+                // meaning the anonymous class's own parent
+                let parent = anon_class
+                    .extends
+                    .as_ref()
+                    .and_then(|extends| extends.types.iter().next())
+                    .map(|parent| self.resolve_class_name(parent.value()));
+                let outer_parent = std::mem::replace(&mut self.current_parent, parent);
+                let outer_in_trait = std::mem::replace(&mut self.in_trait, false);
+                let outer_anonymous = std::mem::replace(&mut self.in_anonymous_class, true);
+                let outer_properties = std::mem::take(&mut self.property_types);
+                let outer_method = self.current_method.clone();
+                for member in anon_class.members.iter() {
+                    self.visit_class_member(member);
+                }
+                self.current_parent = outer_parent;
+                self.in_trait = outer_in_trait;
+                self.in_anonymous_class = outer_anonymous;
+                self.property_types = outer_properties;
+                self.current_method = outer_method;
+            }
+            Expression::Throw(throw_expr) => {
+                self.walk_expression(throw_expr.exception);
+            }
+            Expression::Access(access) => match access {
+                // ClassName::$property
+                Access::StaticProperty(static_prop) => {
+                    self.walk_class_expression(static_prop.class, ReferenceKind::StaticProperty);
+                }
+                // ClassName::CONSTANT, or ClassName::class which names the class without loading it
+                Access::ClassConstant(class_const) => {
+                    let kind = match &class_const.constant {
+                        ClassLikeConstantSelector::Identifier(constant) if constant.value.eq_ignore_ascii_case("class") => {
+                            ReferenceKind::Dynamic
+                        }
+                        _ => ReferenceKind::ConstantAccess,
+                    };
+                    self.walk_class_expression(class_const.class, kind);
+                    if let ClassLikeConstantSelector::Expression(ref selector) = class_const.constant {
+                        self.walk_expression(selector.expression);
+                    }
+                }
+                Access::Property(prop) => {
+                    self.walk_expression(prop.object);
+                    self.walk_member_selector(&prop.property);
+                }
+                Access::NullSafeProperty(prop) => {
+                    self.walk_expression(prop.object);
+                    self.walk_member_selector(&prop.property);
+                }
+            },
+            Expression::Call(call) => match call {
+                // ClassName::method()
+                Call::StaticMethod(static_method) => {
+                    self.add_dynamic_class_site(
+                        static_method.class,
+                        static_method.class.span().start.offset,
+                        static_method.method.span().end.offset,
+                    );
+                    self.walk_class_expression(static_method.class, ReferenceKind::StaticCall);
+                    self.add_method_call(self.static_receiver_class(static_method.class), &static_method.method);
+                    self.walk_member_selector(&static_method.method);
+                    self.walk_argument_list(&static_method.argument_list);
+                }
+                // helper() or $callable()
+                Call::Function(func_call) => {
+                    match func_call.function {
+                        Expression::Identifier(id) => {
+                            self.add_function_call(id);
+                            // The names given to class_alias() declare the alias, they are not
+                            // references to it
+                            if id.value().trim_start_matches('\\').eq_ignore_ascii_case("class_alias")
+                                && self.add_class_alias(expression, &func_call.argument_list)
+                            {
+                                return;
+                            }
+                        }
+                        other => self.walk_expression(other),
+                    }
+                    self.walk_argument_list(&func_call.argument_list);
+                }
+                Call::Method(method_call) => {
+                    self.walk_expression(method_call.object);
+                    self.add_instance_call(method_call.object, &method_call.method);
+                    self.walk_member_selector(&method_call.method);
+                    self.walk_argument_list(&method_call.argument_list);
+                }
+                Call::NullSafeMethod(null_safe) => {
+                    self.walk_expression(null_safe.object);
+                    self.add_instance_call(null_safe.object, &null_safe.method);
+                    self.walk_member_selector(&null_safe.method);
+                    self.walk_argument_list(&null_safe.argument_list);
+                }
+            },
+            Expression::PartialApplication(partial) => match partial {
+                PartialApplication::Function(func) => {
+                    match func.function {
+                        Expression::Identifier(id) => self.add_function_call(id),
+                        other => self.walk_expression(other),
+                    }
+                    self.walk_partial_argument_list(&func.argument_list);
+                }
+                PartialApplication::Method(method) => {
+                    self.walk_expression(method.object);
+                    self.add_instance_call(method.object, &method.method);
+                    self.walk_member_selector(&method.method);
+                    self.walk_partial_argument_list(&method.argument_list);
+                }
+                PartialApplication::StaticMethod(static_method) => {
+                    self.add_dynamic_class_site(
+                        static_method.class,
+                        static_method.class.span().start.offset,
+                        static_method.method.span().end.offset,
+                    );
+                    self.walk_class_expression(static_method.class, ReferenceKind::StaticCall);
+                    self.add_method_call(self.static_receiver_class(static_method.class), &static_method.method);
+                    self.walk_member_selector(&static_method.method);
+                    self.walk_partial_argument_list(&static_method.argument_list);
+                }
+            },
+            // $var instanceof ClassName
+            Expression::Binary(binary) => {
+                self.walk_expression(binary.lhs);
+                if matches!(binary.operator, BinaryOperator::Instanceof(_)) {
+                    self.walk_class_expression(binary.rhs, ReferenceKind::Instanceof);
+                } else {
+                    self.walk_expression(binary.rhs);
+                }
+            }
+            Expression::UnaryPrefix(unary) => {
+                self.walk_expression(unary.operand);
+            }
+            Expression::UnaryPostfix(unary) => {
+                self.walk_expression(unary.operand);
+            }
+            Expression::Parenthesized(paren) => {
+                self.walk_expression(paren.expression);
+            }
+            Expression::Assignment(assign) => {
+                self.walk_expression(assign.lhs);
+                self.walk_expression(assign.rhs);
+                // `$x = new Foo()` types `$x` until it is assigned something else
+                if let Expression::Variable(Variable::Direct(variable)) = assign.lhs {
+                    let class = match (&assign.operator, assign.rhs) {
+                        (AssignmentOperator::Assign(_), Expression::Instantiation(instantiation)) => {
+                            self.class_expression_name(instantiation.class)
+                        }
+                        _ => None,
+                    };
+                    match class {
+                        Some(class) => self.local_types.insert(variable.name.to_string(), class),
+                        None => self.local_types.remove(variable.name),
+                    };
+                }
+            }
+            // $cond ? $a : $b and $a ?: $b
+            Expression::Conditional(conditional) => {
+                self.walk_expression(conditional.condition);
+                if let Some(then) = conditional.then {
+                    self.walk_expression(then);
+                }
+                self.walk_expression(conditional.r#else);
+            }
+            Expression::Array(array) => {
+                self.add_callable_array(array.elements.iter());
+                self.walk_array_elements(array.elements.iter());
+            }
+            Expression::LegacyArray(array) => {
+                self.add_callable_array(array.elements.iter());
+                self.walk_array_elements(array.elements.iter());
+            }
+            Expression::List(list) => {
+                self.walk_array_elements(list.elements.iter());
+            }
+            Expression::ArrayAccess(access) => {
+                self.walk_expression(access.array);
+                self.walk_expression(access.index);
+            }
+            Expression::ArrayAppend(append) => {
+                self.walk_expression(append.array);
+            }
+            Expression::CompositeString(string) => {
+                let parts = match string {
+                    CompositeString::ShellExecute(s) => &s.parts,
+                    CompositeString::Interpolated(s) => &s.parts,
+                    CompositeString::Document(s) => &s.parts,
+                };
+                for part in parts.iter() {
+                    match part {
+                        StringPart::Expression(expr) => self.walk_expression(expr),
+                        StringPart::BracedExpression(braced) => self.walk_expression(braced.expression),
+                        StringPart::Literal(_) => {}
+                    }
+                }
+            }
+            // A closure only sees the variables it imports with `use`
+            Expression::Closure(closure) => {
+                let imported: HashMap<String, String> = closure
+                    .use_clause
+                    .iter()
+                    .flat_map(|clause| clause.variables.iter())
+                    .filter_map(|imported| {
+                        let name = imported.variable.name;
+                        self.local_types.get(name).map(|class| (name.to_string(), class.clone()))
+                    })
+                    .collect();
+                let outer_locals = std::mem::replace(&mut self.local_types, imported);
+                self.walk_attribute_lists(closure.attribute_lists.iter());
+                self.walk_parameter_list(&closure.parameter_list);
+                if let Some(ref return_type) = closure.return_type_hint {
+                    self.walk_hint(&return_type.hint, ReferenceKind::ReturnHint);
+                }
+                self.walk_block(&closure.body);
+                self.local_types = outer_locals;
+            }
+            // An arrow function sees the enclosing scope, but its parameters stay its own
+            Expression::ArrowFunction(arrow_fn) => {
+                let outer_locals = self.local_types.clone();
+                self.walk_attribute_lists(arrow_fn.attribute_lists.iter());
+                self.walk_parameter_list(&arrow_fn.parameter_list);
+                if let Some(ref return_type) = arrow_fn.return_type_hint {
+                    self.walk_hint(&return_type.hint, ReferenceKind::ReturnHint);
+                }
+                self.walk_expression(arrow_fn.expression);
+                self.local_types = outer_locals;
+            }
+            // match($expr) { ClassName::class => ... }
+            Expression::Match(match_expr) => {
+                self.walk_expression(match_expr.expression);
+                for arm in match_expr.arms.iter() {
+                    match arm {
+                        MatchArm::Expression(expr_arm) => {
+                            for condition in expr_arm.conditions.iter() {
+                                self.walk_expression(condition);
+                            }
+                            self.walk_expression(expr_arm.expression);
+                        }
+                        MatchArm::Default(default_arm) => {
+                            self.walk_expression(default_arm.expression);
+                        }
+                    }
+                }
+            }
+            Expression::Yield(yield_expr) => match yield_expr {
+                Yield::Value(y) => {
+                    if let Some(value) = y.value {
+                        self.walk_expression(value);
+                    }
+                }
+                Yield::Pair(y) => {
+                    self.walk_expression(y.key);
+                    self.walk_expression(y.value);
+                }
+                Yield::From(y) => {
+                    self.walk_expression(y.iterator);
+                }
+            },
+            Expression::Construct(construct) => match construct {
+                Construct::Isset(c) => {
+                    for value in c.values.iter() {
+                        self.walk_expression(value);
+                    }
+                }
+                Construct::Empty(c) => self.walk_expression(c.value),
+                Construct::Eval(c) => self.walk_expression(c.value),
+                Construct::Include(c) => self.add_include(IncludeKind::Include, expression, c.value),
+                Construct::IncludeOnce(c) => self.add_include(IncludeKind::IncludeOnce, expression, c.value),
+                Construct::Require(c) => self.add_include(IncludeKind::Require, expression, c.value),
+                Construct::RequireOnce(c) => self.add_include(IncludeKind::RequireOnce, expression, c.value),
+                Construct::Print(c) => self.walk_expression(c.value),
+                Construct::Exit(c) => {
+                    if let Some(ref arg_list) = c.arguments {
+                        self.walk_argument_list(arg_list);
+                    }
+                }
+                Construct::Die(c) => {
+                    if let Some(ref arg_list) = c.arguments {
+                        self.walk_argument_list(arg_list);
+                    }
+                }
+            },
+            Expression::Clone(clone) => {
+                self.walk_expression(clone.object);
+            }
+            Expression::Pipe(pipe) => {
+                self.walk_expression(pipe.input);
+                self.walk_expression(pipe.callable);
+            }
+            Expression::Variable(variable) => {
+                self.walk_variable(variable);
+            }
+            Expression::Literal(Literal::String(string)) => self.add_class_string(string),
+            // Bare identifiers outside a class position are function or constant names;
+            // other literals, magic constants and self/static/parent keywords carry no class name
+            _ => {}
+        }
+    }
+
+    fn walk_hint(&mut self, hint: &Hint, kind: ReferenceKind) {
+        match hint {
+            Hint::Identifier(id) => self.add_reference(id, kind),
+            Hint::Self_(keyword) | Hint::Static(keyword) | Hint::Parent(keyword) => {
+                self.add_keyword_reference(keyword, kind)
+            }
+            Hint::Parenthesized(p) => self.walk_hint(p.hint, kind),
+            Hint::Nullable(n) => self.walk_hint(n.hint, kind),
+            Hint::Union(u) => {
+                self.walk_hint(u.left, kind);
+                self.walk_hint(u.right, kind);
+            }
+            Hint::Intersection(i) => {
+                self.walk_hint(i.left, kind);
+                self.walk_hint(i.right, kind);
+            }
+            _ => {}
+        }
+    }
+
+    fn walk_attribute_lists<'s, 'a: 's>(&mut self, attribute_lists: impl Iterator<Item = &'s AttributeList<'a>>) {
+        for attribute_list in attribute_lists {
+            for attribute in attribute_list.attributes.iter() {
+                self.add_reference(&attribute.name, ReferenceKind::Attribute);
+                if let Some(ref arg_list) = attribute.argument_list {
+                    self.walk_argument_list(arg_list);
+                }
+            }
+        }
+    }
+
+    fn walk_parameter_list(&mut self, parameter_list: &FunctionLikeParameterList) {
+        for param in parameter_list.parameters.iter() {
+            self.walk_attribute_lists(param.attribute_lists.iter());
+            if let Some(ref hint) = param.hint {
+                self.walk_hint(hint, ReferenceKind::ParameterHint);
+                if let Some(class) = self.hint_class(hint) {
+                    self.local_types.insert(param.variable.name.to_string(), class);
+                }
+            }
+            if let Some(ref default_value) = param.default_value {
+                self.walk_expression(&default_value.value);
+            }
+        }
+    }
+
+    /// Walk the default value of a property
+    fn walk_property_item(&mut self, item: &PropertyItem) {
+        if let PropertyItem::Concrete(item) = item {
+            self.walk_expression(&item.value);
+        }
+    }
+
+    fn walk_argument_list(&mut self, arg_list: &ArgumentList) {
+        for arg in arg_list.arguments.iter() {
+            match arg {
+                Argument::Positional(pos) => self.walk_expression(&pos.value),
+                Argument::Named(named) => self.walk_expression(&named.value),
+            }
+        }
+    }
+
+    fn walk_partial_argument_list(&mut self, arg_list: &PartialArgumentList) {
+        for arg in arg_list.arguments.iter() {
+            match arg {
+                PartialArgument::Positional(pos) => self.walk_expression(&pos.value),
+                PartialArgument::Named(named) => self.walk_expression(&named.value),
+                PartialArgument::NamedPlaceholder(_)
+                | PartialArgument::Placeholder(_)
+                | PartialArgument::VariadicPlaceholder(_) => {}
+            }
+        }
+    }
+
+    fn walk_array_elements<'s, 'a: 's>(&mut self, elements: impl Iterator<Item = &'s ArrayElement<'a>>) {
+        for element in elements {
+            match element {
+                ArrayElement::KeyValue(kv) => {
+                    self.walk_expression(kv.key);
+                    self.walk_expression(kv.value);
+                }
+                ArrayElement::Value(v) => self.walk_expression(v.value),
+                ArrayElement::Variadic(v) => self.walk_expression(v.value),
+                ArrayElement::Missing(_) => {}
+            }
+        }
+    }
+
+    fn walk_member_selector(&mut self, selector: &ClassLikeMemberSelector) {
+        match selector {
+            ClassLikeMemberSelector::Variable(variable) => self.walk_variable(variable),
+            ClassLikeMemberSelector::Expression(selector) => self.walk_expression(selector.expression),
+            ClassLikeMemberSelector::Identifier(_) => {}
+        }
+    }
+
+    fn walk_variable(&mut self, variable: &Variable) {
+        match variable {
+            Variable::Indirect(indirect) => self.walk_expression(indirect.expression),
+            Variable::Nested(nested) => self.walk_variable(nested.variable),
+            Variable::Direct(_) => {}
+        }
+    }
+}
+
+/// Extract an in-memory file, for tests
+#[cfg(test)]
+pub(crate) fn extract_source(path: &str, content: &str) -> FileModel {
+    extract_file(&File::ephemeral(path.to_string().into(), content.to_string().into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn references_in(code: &str) -> Vec<String> {
+        let file = extract_source("test.php", code);
+        file.references.into_iter().map(|r| r.to).collect()
+    }
+
+    fn method_body(body: &str) -> String {
+        format!("<?php\nnamespace App;\nuse Lib\\A;\nclass Subject {{\n    public function run() {{\n{}\n    }}\n}}\n", body)
+    }
+
+    #[test]
+    fn test_walks_control_flow() {
+        let refs = references_in(&method_body(r#"
+if ($a) { new A(); } elseif ($b) { B::make(); } else { echo C::NAME; }
+foreach ($items as $item) { if ($item instanceof D) {} }
+while (true) { switch ($x) { case E::ONE: new F(); break; } }
+for ($i = G::start(); $i < 10; $i++) {}
+do { H::$cache = null; } while (false);
+"#));
+        assert_eq!(refs, vec!["Lib\\A", "App\\B", "App\\C", "App\\D", "App\\E", "App\\F", "App\\G", "App\\H"]);
+    }
+
+    #[test]
+    fn test_walks_try_catch_finally() {
+        let refs = references_in(&method_body(r#"
+try { new A(); } catch (B | C $e) { new D(); } finally { new E(); }
+"#));
+        assert_eq!(refs, vec!["Lib\\A", "App\\B", "App\\C", "App\\D", "App\\E"]);
+    }
+
+    #[test]
+    fn test_walks_closures_arrays_strings_and_ternaries() {
+        let refs = references_in(&method_body(r#"
+$f = function (A $a): B { return new C(); };
+$g = fn(D $d) => E::make();
+$list = [F::class, 'key' => new G()];
+$s = "value: {$x->build(new H())}";
+$t = $cond ? new I() : J::default();
+"#));
+        assert_eq!(refs, vec!["Lib\\A", "App\\B", "App\\C", "App\\D", "App\\E", "App\\F", "App\\G", "App\\H", "App\\I", "App\\J"]);
+    }
+
+    #[test]
+    fn test_extracts_symbols_and_imports() {
+        let file = extract_source("src/User.php", r#"<?php
+namespace App\Models;
+
+use App\Contracts\Authenticatable as Auth;
+
+class User extends Model implements Auth {}
+interface HasName {}
+"#);
+
+        let symbols: Vec<_> = file.symbols.iter().map(|s| (s.fqn.as_str(), s.kind)).collect();
+        assert_eq!(symbols, vec![("App\\Models\\User", SymbolKind::Class), ("App\\Models\\HasName", SymbolKind::Interface)]);
+        assert_eq!(file.imports[0].alias, "Auth");
+        assert_eq!(file.imports[0].fqn, "App\\Contracts\\Authenticatable");
+        assert_eq!(file.imports[0].kind, ImportKind::Class);
+
+        let refs: Vec<_> = file.references.iter().map(|r| (r.from.as_str(), r.to.as_str(), r.kind)).collect();
+        assert_eq!(refs, vec![
+            ("App\\Models\\User", "App\\Models\\Model", ReferenceKind::Extends),
+            ("App\\Models\\User", "App\\Contracts\\Authenticatable", ReferenceKind::Implements),
+        ]);
+    }
+
+    #[test]
+    fn test_records_reference_kinds() {
+        let file = extract_source("test.php", r#"<?php
+namespace App;
+#[Route]
+class Subject {
+    use Loggable;
+    private Repo $repo;
+    public function run(Input $in): Output {
+        new A();
+        B::make();
+        C::$cache;
+        D::NAME;
+        if ($in instanceof E) {}
+        try {} catch (F $e) {}
+    }
+}
+"#);
+
+        let kinds: Vec<_> = file.references.iter().map(|r| (r.to.as_str(), r.kind)).collect();
+        assert_eq!(kinds, vec![
+            ("App\\Route", ReferenceKind::Attribute),
+            ("App\\Loggable", ReferenceKind::TraitUse),
+            ("App\\Repo", ReferenceKind::PropertyHint),
+            ("App\\Output", ReferenceKind::ReturnHint),
+            ("App\\Input", ReferenceKind::ParameterHint),
+            ("App\\A", ReferenceKind::Instantiation),
+            ("App\\B", ReferenceKind::StaticCall),
+            ("App\\C", ReferenceKind::StaticProperty),
+            ("App\\D", ReferenceKind::ConstantAccess),
+            ("App\\E", ReferenceKind::Instanceof),
+            ("App\\F", ReferenceKind::Catch),
+        ]);
+
+        let methods: Vec<_> = file.references.iter().map(|r| r.method.as_deref()).collect();
+        assert_eq!(&methods[..4], &[None, None, None, Some("run")]);
+    }
+
+    #[test]
+    fn test_grouped_function_and_const_imports() {
+        let file = extract_source("test.php", r#"<?php
+namespace App\Http;
+
+use App\Models\{User, Address as Addr, function helper, const LIMIT};
+use function App\Support\{format_date, format_money};
+use const App\Config\DEBUG;
+use App\Services;
+
+class Controller {
+    public function show(User $user, Addr $address): Services\Mailer {
+        return helper(format_date(LIMIT));
+    }
+}
+"#);
+
+        let imports: Vec<_> = file.imports.iter().map(|i| (i.kind, i.alias.as_str(), i.fqn.as_str())).collect();
+        assert_eq!(imports, vec![
+            (ImportKind::Class, "User", "App\\Models\\User"),
+            (ImportKind::Class, "Addr", "App\\Models\\Address"),
+            (ImportKind::Function, "helper", "App\\Models\\helper"),
+            (ImportKind::Const, "LIMIT", "App\\Models\\LIMIT"),
+            (ImportKind::Function, "format_date", "App\\Support\\format_date"),
+            (ImportKind::Function, "format_money", "App\\Support\\format_money"),
+            (ImportKind::Const, "DEBUG", "App\\Config\\DEBUG"),
+            (ImportKind::Class, "Services", "App\\Services"),
+        ]);
+
+        let refs: Vec<_> = file.references.iter().map(|r| r.to.as_str()).collect();
+        assert_eq!(refs, vec![
+            "App\\Services\\Mailer",
+            "App\\Models\\User",
+            "App\\Models\\Address",
+            "App\\Models\\helper()",
+            "App\\Support\\format_date()",
+        ]);
+    }
+
+    #[test]
+    fn test_records_docblock_types() {
+        let file = extract_source("test.php", r#"<?php
+namespace App;
+use Shop\Order;
+
+/**
+ * @template TItem of Item
+ * @extends Collection<int, TItem>
+ */
+class Orders extends Collection {
+    /** @var Repo */
+    private $repo;
+
+    /**
+     * @template T
+     * @param class-string<T> $class
+     * @return array<int, Order>
+     * @throws NotFound
+     */
+    public function find($class) {
+        /** @var Cart $cart */
+        $cart = $this->repo->cart();
+    }
+}
+"#);
+
+        let refs: Vec<_> = file
+            .references
+            .iter()
+            .filter(|r| r.kind == ReferenceKind::Docblock)
+            .map(|r| (r.to.as_str(), r.method.as_deref(), r.location.line))
+            .collect();
+        assert_eq!(refs, vec![
+            ("App\\Item", None, 6),
+            ("App\\Collection", None, 7),
+            ("App\\Repo", None, 10),
+            ("Shop\\Order", Some("find"), 16),
+            ("App\\NotFound", Some("find"), 17),
+            ("App\\Cart", Some("find"), 20),
+        ]);
+    }
+
+    #[test]
+    fn test_walks_every_class_like_member() {
+        let file = extract_source("test.php", r#"<?php
+namespace App;
+interface Repository {
+    const DEFAULT = Status::Active;
+    public function find(Id $id): ?Entity;
+}
+enum Status: string implements HasLabel {
+    case Active = Prefix::VALUE . 'active';
+    const FALLBACK = Fallback::NAME;
+    public function label(): string { return Translator::get($this->value); }
+}
+class Service {
+    private array $handlers = [Handler::class];
+    public function __construct(#[Inject] private Logger $logger) {}
+    public function make(): object {
+        return new class(Config::load()) extends Base implements Runnable {
+            use Retries;
+            public function run() { Runner::start(); }
+        };
+    }
+}
+"#);
+
+        let refs: Vec<_> = file.references.iter().map(|r| (r.from.as_str(), r.to.as_str())).collect();
+        assert_eq!(refs, vec![
+            ("App\\Repository", "App\\Status"),
+            ("App\\Repository", "App\\Entity"),
+            ("App\\Repository", "App\\Id"),
+            ("App\\Status", "App\\HasLabel"),
+            ("App\\Status", "App\\Prefix"),
+            ("App\\Status", "App\\Fallback"),
+            ("App\\Status", "App\\Translator"),
+            ("App\\Service", "App\\Handler"),
+            ("App\\Service", "App\\Inject"),
+            ("App\\Service", "App\\Logger"),
+            ("App\\Service", "App\\Config"),
+            ("App\\Service", "App\\Base"),
+            ("App\\Service", "App\\Runnable"),
+            ("App\\Service", "App\\Retries"),
+            ("App\\Service", "App\\Runner"),
+        ]);
+
+        // `Service` declares no `run`; the anonymous class's method is part of `make`
+        let runner = file.references.iter().find(|r| r.to == "App\\Runner").unwrap();
+        assert_eq!(runner.method.as_deref(), Some("make"));
+        // The anonymous class's parent, interface and trait are not inherited by `Service`
+        let anonymous: Vec<_> = file.references[11..14].iter().map(|r| r.kind).collect();
+        assert_eq!(anonymous, vec![ReferenceKind::Instantiation; 3]);
+    }
+
+    #[test]
+    fn test_resolves_self_parent_and_static() {
+        let class = extract_source("src/Report.php", r#"<?php
+namespace App;
+class Report extends Base {
+    use Exports;
+    public function __construct() {
+        parent::__construct();
+        return new static();
+    }
+}
+"#);
+        let traits = extract_source("src/Exports.php", r#"<?php
+namespace App;
+trait Exports {
+    use Formats;
+    public static function export(): static {
+        parent::boot();
+        return self::create(new Writer());
+    }
+}
+trait Formats {
+    public function format() { return Formatter::for(static::class); }
+}
+"#);
+
+        let class_refs: Vec<_> = class.references.iter().map(|r| (r.to.as_str(), r.kind)).collect();
+        assert_eq!(class_refs, vec![
+            ("App\\Base", ReferenceKind::Extends),
+            ("App\\Exports", ReferenceKind::TraitUse),
+            ("App\\Base", ReferenceKind::StaticCall),
+            ("App\\Report", ReferenceKind::Instantiation),
+        ]);
+        let trait_refs: Vec<_> = traits.references.iter().map(|r| r.to.as_str()).collect();
+        assert_eq!(trait_refs, vec![
+            "App\\Formats",
+            "App\\Exports",
+            TRAIT_PARENT,
+            "App\\Exports",
+            "App\\Writer",
+            "App\\Formatter",
+            "App\\Formats",
+        ]);
+
+        // Trait members, including those of nested traits, become dependencies of the class
+        let mut model = crate::model::CodeModel::new();
+        model.add_file(class);
+        model.add_file(traits);
+        model.apply_trait_uses();
+
+        let mut copied: Vec<_> = model
+            .references()
+            .filter(|r| r.from == "App\\Report" && r.location.file == "src/Exports.php")
+            .map(|r| (r.to.as_str(), r.kind, r.method.as_deref()))
+            .collect();
+        copied.sort();
+        assert_eq!(copied, vec![
+            ("App\\Base", ReferenceKind::StaticCall, Some("export")),
+            ("App\\Formats", ReferenceKind::TraitUse, None),
+            ("App\\Formatter", ReferenceKind::StaticCall, Some("format")),
+            ("App\\Report", ReferenceKind::StaticCall, Some("export")),
+            ("App\\Report", ReferenceKind::ReturnHint, Some("export")),
+            ("App\\Report", ReferenceKind::Dynamic, Some("format")),
+            ("App\\Writer", ReferenceKind::Instantiation, Some("export")),
+        ]);
+        assert!(model.references().all(|r| r.to != TRAIT_PARENT));
+    }
+
+    #[test]
+    fn test_trait_parent_is_the_declared_parent() {
+        let class = extract_source("src/Report.php", r#"<?php
+namespace App;
+class Report extends Base {
+    use Boots;
+    public function make() {
+        return new class extends Other {};
+    }
+}
+"#);
+        let traits = extract_source("src/Boots.php", r#"<?php
+namespace App;
+trait Boots {
+    public static function boot() { parent::boot(); }
+}
+"#);
+        assert_eq!(class.symbols[0].parents, vec!["App\\Base"]);
+
+        let mut model = crate::model::CodeModel::new();
+        model.add_file(class);
+        model.add_file(traits);
+        model.apply_trait_uses();
+
+        let parent_calls: Vec<_> = model
+            .references()
+            .filter(|r| r.from == "App\\Report" && r.kind == ReferenceKind::StaticCall)
+            .map(|r| r.to.as_str())
+            .collect();
+        assert_eq!(parent_calls, vec!["App\\Base"]);
+    }
+
+    #[test]
+    fn test_models_functions_and_scripts() {
+        let helpers = extract_source("src/helpers.php", r#"<?php
+namespace App;
+use function Vendor\format_money;
+
+/** @param Order $order */
+function total(Order $order): Money {
+    $f = fn () => Tax::rate();
+    return format_money(helper(strlen($order->id)));
+}
+function helper($n) { return \App\total(new Order()); }
+"#);
+        let script = extract_source("public/index.php", r#"<?php
+require __DIR__ . '/../vendor/autoload.php';
+$app = new App\Kernel();
+echo App\total($app->order());
+"#);
+
+        let symbols: Vec<_> = helpers.symbols.iter().chain(&script.symbols).map(|s| (s.fqn.as_str(), s.kind)).collect();
+        assert_eq!(symbols, vec![
+            ("App\\total()", SymbolKind::Function),
+            ("App\\helper()", SymbolKind::Function),
+            ("public/index.php", SymbolKind::Script),
+        ]);
+
+        let refs: Vec<_> = helpers
+            .references
+            .iter()
+            .chain(&script.references)
+            .map(|r| (r.from.as_str(), r.to.as_str(), r.kind))
+            .collect();
+        assert_eq!(refs, vec![
+            ("App\\total()", "App\\Order", ReferenceKind::Docblock),
+            ("App\\total()", "App\\Order", ReferenceKind::ParameterHint),
+            ("App\\total()", "App\\Money", ReferenceKind::ReturnHint),
+            ("App\\total()", "App\\Tax", ReferenceKind::StaticCall),
+            ("App\\total()", "Vendor\\format_money()", ReferenceKind::FunctionCall),
+            ("App\\total()", "App\\helper()", ReferenceKind::FunctionCall),
+            ("App\\total()", "App\\strlen()", ReferenceKind::FunctionCall),
+            ("App\\helper()", "App\\total()", ReferenceKind::FunctionCall),
+            ("App\\helper()", "App\\Order", ReferenceKind::Instantiation),
+            ("public/index.php", "App\\Kernel", ReferenceKind::Instantiation),
+            ("public/index.php", "App\\total()", ReferenceKind::FunctionCall),
+            ("public/index.php", "App\\Kernel", ReferenceKind::MethodCall),
+        ]);
+
+        // Calls to functions the namespace does not declare fall back to the global one
+        let mut model = crate::model::CodeModel::new();
+        model.add_file(helpers);
+        model.resolve_function_fallbacks();
+        assert!(model.references().any(|r| r.to == "strlen()"));
+        assert!(model.references().any(|r| r.to == "App\\helper()"));
+
+        // Qualified calls are never resolved against the global namespace
+        let mut model = crate::model::CodeModel::new();
+        model.add_file(extract_source("src/calls.php", r#"<?php
+namespace App;
+\App\strlen('a');
+namespace\count([]);
+trim(' ');
+"#));
+        model.resolve_function_fallbacks();
+        let targets: Vec<_> = model.references().map(|r| r.to.as_str()).collect();
+        assert_eq!(targets, vec!["App\\strlen()", "App\\count()", "trim()"]);
+    }
+
+    #[test]
+    fn test_self_and_static_name_no_class_outside_class_likes() {
+        let file = extract_source("src/helpers.php", r#"<?php
+namespace App;
+function make(): static {
+    static::x();
+    return new self();
+}
+self::boot();
+"#);
+
+        assert!(file.references.is_empty());
+        assert!(file.calls.is_empty());
+    }
+
+    #[test]
+    fn test_infers_receiver_classes() {
+        let file = extract_source("src/Checkout.php", r#"<?php
+namespace App;
+use Lib\Mailer;
+class Checkout {
+    /** @var Logger */
+    private $logger;
+    public function __construct(private Cart $cart, $unused) {}
+    public function pay(Gateway $gateway) {
+        $this->cart->total();
+        $this->logger?->info();
+        $gateway->charge();
+        /** @var Mailer $mailer */
+        $mailer = $this->container->get('mailer');
+        $mailer->send();
+        $receipt = new Receipt();
+        $receipt->print();
+        $receipt = $this->render();
+        $receipt->print();
+        $this->render();
+    }
+}
+"#);
+
+        let refs: Vec<_> = file
+            .references
+            .iter()
+            .filter(|r| r.kind == ReferenceKind::MethodCall)
+            .map(|r| (r.method.as_deref(), r.to.as_str(), r.location.line))
+            .collect();
+        assert_eq!(refs, vec![
+            (Some("pay"), "App\\Cart", 9),
+            (Some("pay"), "App\\Logger", 10),
+            (Some("pay"), "App\\Gateway", 11),
+            (Some("pay"), "Lib\\Mailer", 14),
+            (Some("pay"), "App\\Receipt", 16),
+        ]);
+
+        let calls: Vec<_> = file.calls.iter().map(|c| c.to.as_str()).collect();
+        assert_eq!(calls, vec![
+            "App\\Cart::total",
+            "App\\Logger::info",
+            "App\\Gateway::charge",
+            "Lib\\Mailer::send",
+            "App\\Receipt::print",
+            "App\\Checkout::render",
+            "App\\Checkout::render",
+        ]);
+    }
+
+    #[test]
+    fn test_closure_parameters_stay_in_the_closure() {
+        let file = extract_source("src/Report.php", r#"<?php
+namespace App;
+class Report {
+    public function make(Mailer $mailer, Queue $queue) {
+        $save = function (Other $o) { $o->save(); };
+        $o->save();
+        $send = function () use ($mailer) { $mailer->send(); $queue->push(); };
+        $log = fn (Logger $l) => $l->write() && $queue->push();
+        $l->write();
+    }
+}
+"#);
+
+        let calls: Vec<_> = file.calls.iter().map(|c| (c.to.as_str(), c.location.line)).collect();
+        assert_eq!(calls, vec![
+            ("App\\Other::save", 5),
+            ("App\\Mailer::send", 7),
+            ("App\\Logger::write", 8),
+            ("App\\Queue::push", 8),
+        ]);
+    }
+
+    #[test]
+    fn test_records_dynamic_class_references() {
+        let file = extract_source("src/Kernel.php", r#"<?php
+namespace App;
+class Kernel {
+    public function boot($container, string $className) {
+        $container->get(Services\Mailer::class);
+        $user = 'App\\Models\\User';
+        if (class_exists('\\App\\Models\\Admin')) {}
+        $container->on('saved', [Listeners\Audit::class, 'handle']);
+        $container->on('deleted', 'App\\Listeners\\Audit::purge');
+        echo 'Hello World', 'App\\Missing', 'user';
+        $handler = new $className();
+        $className::boot();
+        $logger = new Logger();
+        $logger::level();
+    }
+}
+"#);
+        let mut model = crate::model::CodeModel::new();
+        model.add_file(extract_source("src/Models.php", "<?php\nnamespace App\\Models;\nclass User {}\nclass Admin {}\n"));
+        model.add_file(extract_source("src/Audit.php", "<?php\nnamespace App\\Listeners;\nclass Audit {}\n"));
+        model.add_file(file);
+        model.resolve_class_strings();
+
+        let refs: Vec<_> = model
+            .references()
+            .filter(|r| r.kind == ReferenceKind::Dynamic)
+            .map(|r| (r.to.as_str(), r.location.line, r.location.column))
+            .collect();
+        assert_eq!(refs, vec![
+            ("App\\Services\\Mailer", 5, 25),
+            ("App\\Listeners\\Audit", 8, 34),
+            ("App\\Models\\User", 6, 18),
+            ("App\\Models\\Admin", 7, 27),
+            ("App\\Listeners\\Audit", 9, 36),
+        ]);
+
+        let calls: Vec<_> = model.files[2].calls.iter().map(|c| c.to.as_str()).collect();
+        assert_eq!(calls, vec!["App\\Listeners\\Audit::handle", "App\\Logger::level"]);
+
+        let sites: Vec<_> = model
+            .diagnostics()
+            .map(|d| (d.kind, d.location.line, d.message.as_str()))
+            .collect();
+        assert_eq!(sites, vec![
+            (DiagnosticKind::DynamicClass, 11, "`new $className` names its class at runtime"),
+            (DiagnosticKind::DynamicClass, 12, "`$className::boot` names its class at runtime"),
+        ]);
+    }
+
+    #[test]
+    fn test_declares_conditional_classes() {
+        let file = extract_source("polyfill/compat.php", r#"<?php
+namespace Compat;
+if (!class_exists(Json::class)) {
+    class Json extends Codec { public function encode(Value $v) {} }
+}
+new Json();
+"#);
+
+        let symbols: Vec<_> = file.symbols.iter().map(|s| (s.fqn.as_str(), s.kind, s.line)).collect();
+        assert_eq!(symbols, vec![
+            ("polyfill/compat.php", SymbolKind::Script, 3),
+            ("Compat\\Json", SymbolKind::Class, 4),
+        ]);
+        let refs: Vec<_> = file.references.iter().map(|r| (r.from.as_str(), r.to.as_str(), r.kind)).collect();
+        assert_eq!(refs, vec![
+            ("polyfill/compat.php", "Compat\\class_exists()", ReferenceKind::FunctionCall),
+            ("polyfill/compat.php", "Compat\\Json", ReferenceKind::Dynamic),
+            ("Compat\\Json", "Compat\\Codec", ReferenceKind::Extends),
+            ("Compat\\Json", "Compat\\Value", ReferenceKind::ParameterHint),
+            ("polyfill/compat.php", "Compat\\Json", ReferenceKind::Instantiation),
+        ]);
+    }
+
+    #[test]
+    fn test_reports_parse_errors_as_diagnostics() {
+        let file = extract_source("src/Broken.php", "<?php\nnamespace App;\n\nclass Broken extends Base {\n    public function run() {\n        $x = ;\n    }\n}\n");
+
+        assert_eq!(file.diagnostics.len(), 1);
+        let location = &file.diagnostics[0].location;
+        assert_eq!((location.file.as_str(), location.line), ("src/Broken.php", 6));
+        assert!(!file.diagnostics[0].message.is_empty());
+    }
+}
@@ -0,0 +1,411 @@
+use anyhow::{Context, Result};
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use indexmap::IndexMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Name of the project-specific ignore file, read with `.gitignore` syntax
+pub const IGNORE_FILE: &str = ".modfatherignore";
+
+/// Number of bytes at the start of a file searched for an `@generated` marker
+const GENERATED_MARKER_WINDOW: u64 = 2048;
+
+/// One line of a `.gitignore`-style file
+#[derive(Debug)]
+struct IgnoreRule {
+    matcher: globset::GlobMatcher,
+    negated: bool,
+    dir_only: bool,
+}
+
+/// Rules read from a `.gitignore`-style file. The last matching rule wins and
+/// `!pattern` re-includes a path.
+#[derive(Debug, Default)]
+pub struct IgnoreRules {
+    rules: Vec<IgnoreRule>,
+}
+
+impl IgnoreRules {
+    /// Read rules from a file, returning no rules if it does not exist
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read ignore file: {}", path.display()))?;
+        Self::parse(&content)
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut rules = Vec::new();
+        for line in content.lines() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (negated, pattern) = match line.strip_prefix('!') {
+                Some(rest) => (true, rest),
+                None => (false, line.strip_prefix('\\').unwrap_or(line)),
+            };
+            let (dir_only, pattern) = match pattern.strip_suffix('/') {
+                Some(rest) => (true, rest),
+                None => (false, pattern),
+            };
+
+            // A pattern with a slash is anchored to the ignore file's directory,
+            // one without matches at any depth
+            let glob = match pattern.strip_prefix('/') {
+                Some(anchored) => anchored.to_string(),
+                None if pattern.contains('/') => pattern.to_string(),
+                None => format!("**/{}", pattern),
+            };
+
+            let matcher = GlobBuilder::new(&glob)
+                .literal_separator(true)
+                .build()
+                .with_context(|| format!("Invalid ignore pattern: {}", line))?
+                .compile_matcher();
+            rules.push(IgnoreRule { matcher, negated, dir_only });
+        }
+        Ok(Self { rules })
+    }
+
+    /// Whether a path relative to the ignore file's directory is ignored (`Some(true)`),
+    /// re-included (`Some(false)`) or not matched by any rule
+    fn matched(&self, relative: &Path, is_dir: bool) -> Option<bool> {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| (is_dir || !rule.dir_only) && rule.matcher.is_match(relative))
+            .map(|rule| !rule.negated)
+    }
+}
+
+/// An ignore file and the directory its patterns are relative to
+#[derive(Debug)]
+struct IgnoreFile {
+    /// Reported as the rule excluding a path, e.g. `src/.gitignore`
+    name: String,
+    /// Directory of the file relative to the scanned directory, empty for the
+    /// scanned directory and the directories above it
+    dir: PathBuf,
+    /// Path of the scanned directory relative to the file, for the directories above it
+    prefix: PathBuf,
+    rules: IgnoreRules,
+}
+
+/// The ignore files applying while a directory is scanned: those of the
+/// directories above it up to the root of its git repository, then those found
+/// in each directory walked. As in git, the file closest to a path decides
+/// whether it is ignored.
+#[derive(Debug, Default)]
+pub struct IgnoreStack {
+    /// Names of the ignore files read in each directory
+    names: Vec<&'static str>,
+    /// Ignore files from the outermost directory in
+    files: Vec<IgnoreFile>,
+}
+
+impl IgnoreStack {
+    /// Load the ignore files of `root` and of the directories above it, up to the
+    /// enclosing git repository's root. Outside a repository only `root`'s own are read.
+    fn new(names: Vec<&'static str>, root: &Path) -> Result<Self> {
+        let mut stack = Self {
+            names,
+            files: Vec::new(),
+        };
+
+        let root_absolute = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        let mut parents = Vec::new();
+        if !root_absolute.join(".git").exists() {
+            for parent in root_absolute.ancestors().skip(1) {
+                parents.push(parent);
+                if parent.join(".git").exists() {
+                    break;
+                }
+            }
+            if !parents.last().is_some_and(|parent| parent.join(".git").exists()) {
+                parents.clear();
+            }
+        }
+        for parent in parents.into_iter().rev() {
+            let prefix = root_absolute.strip_prefix(parent).unwrap_or(Path::new("")).to_path_buf();
+            stack.load(parent, Path::new(""), prefix)?;
+        }
+
+        stack.enter_dir(root, Path::new(""))?;
+        Ok(stack)
+    }
+
+    /// Load the ignore files of a walked directory, `relative` to the scanned directory
+    pub fn enter_dir(&mut self, dir: &Path, relative: &Path) -> Result<()> {
+        self.load(dir, relative, PathBuf::new())
+    }
+
+    fn load(&mut self, dir: &Path, relative: &Path, prefix: PathBuf) -> Result<()> {
+        for name in self.names.clone() {
+            let path = dir.join(name);
+            if !path.is_file() {
+                continue;
+            }
+            // Files inside the scanned directory are named relative to it
+            let display = if prefix.as_os_str().is_empty() {
+                relative.join(name)
+            } else {
+                path.clone()
+            };
+            self.files.push(IgnoreFile {
+                name: display.display().to_string(),
+                dir: relative.to_path_buf(),
+                prefix: prefix.clone(),
+                rules: IgnoreRules::load(&path)?,
+            });
+        }
+        Ok(())
+    }
+
+    /// Name of the ignore file excluding a path relative to the scanned directory.
+    /// Its parent directories are not checked, as ignored directories are not walked.
+    fn ignored_by(&self, relative: &Path, is_dir: bool) -> Option<&str> {
+        let mut ignored_by = None;
+        for file in &self.files {
+            let Ok(inner) = relative.strip_prefix(&file.dir) else {
+                continue;
+            };
+            if let Some(ignored) = file.rules.matched(&file.prefix.join(inner), is_dir) {
+                ignored_by = ignored.then_some(file.name.as_str());
+            }
+        }
+        ignored_by
+    }
+}
+
+/// Decides which discovered PHP files are analyzed, and counts what each rule excluded
+#[derive(Debug, Default)]
+pub struct FileFilter {
+    include: Option<GlobSet>,
+    exclude: Vec<(String, globset::GlobMatcher)>,
+    respect_gitignore: bool,
+    skip_generated: bool,
+    /// Number of files excluded per rule, in the order rules were first hit
+    excluded: IndexMap<String, usize>,
+}
+
+impl FileFilter {
+    /// A filter with no patterns that honours `.gitignore` and skips `@generated` files
+    pub fn new() -> Self {
+        Self {
+            respect_gitignore: true,
+            skip_generated: true,
+            ..Self::default()
+        }
+    }
+
+    /// Only analyze files matching at least one of these globs
+    pub fn with_include(mut self, patterns: &[String]) -> Result<Self> {
+        if patterns.is_empty() {
+            return Ok(self);
+        }
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            builder.add(Self::glob(pattern)?);
+        }
+        self.include = Some(builder.build()?);
+        Ok(self)
+    }
+
+    /// Never analyze files matching one of these globs
+    pub fn with_exclude(mut self, patterns: &[String]) -> Result<Self> {
+        for pattern in patterns {
+            self.exclude.push((pattern.clone(), Self::glob(pattern)?.compile_matcher()));
+        }
+        Ok(self)
+    }
+
+    pub fn respect_gitignore(mut self, respect: bool) -> Self {
+        self.respect_gitignore = respect;
+        self
+    }
+
+    pub fn skip_generated(mut self, skip: bool) -> Self {
+        self.skip_generated = skip;
+        self
+    }
+
+    fn glob(pattern: &str) -> Result<Glob> {
+        GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .with_context(|| format!("Invalid glob pattern: {}", pattern))
+    }
+
+    /// Load the ignore files that apply to a scanned directory and those above it
+    pub fn ignore_stack(&self, root: &Path) -> Result<IgnoreStack> {
+        let mut names = Vec::new();
+        if self.respect_gitignore {
+            names.push(".gitignore");
+        }
+        names.push(IGNORE_FILE);
+        IgnoreStack::new(names, root)
+    }
+
+    /// Whether a directory should be walked. `relative` is its path relative to
+    /// the scanned directory; an ignored directory counts as one exclusion.
+    pub fn accept_dir(&mut self, relative: &Path, ignores: &IgnoreStack) -> bool {
+        match ignores.ignored_by(relative, true) {
+            Some(name) => {
+                *self.excluded.entry(name.to_string()).or_insert(0) += 1;
+                false
+            }
+            None => true,
+        }
+    }
+
+    /// Whether a file should be analyzed. `relative` is its path relative to the
+    /// scanned directory and `ignores` the ignore files of the directories above it.
+    pub fn accept(&mut self, path: &Path, relative: &Path, ignores: &IgnoreStack) -> bool {
+        let reason = self.rejection(path, relative, ignores);
+        match reason {
+            Some(reason) => {
+                *self.excluded.entry(reason).or_insert(0) += 1;
+                false
+            }
+            None => true,
+        }
+    }
+
+    fn rejection(&self, path: &Path, relative: &Path, ignores: &IgnoreStack) -> Option<String> {
+        if let Some(include) = &self.include {
+            if !include.is_match(relative) {
+                return Some("not matching --include".to_string());
+            }
+        }
+        if let Some((pattern, _)) = self.exclude.iter().find(|(_, m)| m.is_match(relative)) {
+            return Some(format!("--exclude {}", pattern));
+        }
+        if let Some(name) = ignores.ignored_by(relative, false) {
+            return Some(name.to_string());
+        }
+        if self.skip_generated && is_generated(path) {
+            return Some("@generated".to_string());
+        }
+        None
+    }
+
+    /// Number of excluded files, and directories for ignore files, per rule
+    pub fn excluded(&self) -> &IndexMap<String, usize> {
+        &self.excluded
+    }
+}
+
+/// Whether the start of a file carries an `@generated` marker
+fn is_generated(path: &Path) -> bool {
+    let Ok(file) = std::fs::File::open(path) else {
+        return false;
+    };
+    let mut head = Vec::new();
+    if file.take(GENERATED_MARKER_WINDOW).read_to_end(&mut head).is_err() {
+        return false;
+    }
+    String::from_utf8_lossy(&head).contains("@generated")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::PhpFileDiscovery;
+
+    fn write_tree(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("php-modfather-{}", name));
+        let _ = std::fs::remove_dir_all(&dir);
+        for (path, content) in files {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn test_gitignore_rules() {
+        let rules = IgnoreRules::parse("# comment\nvendor/\n/var/cache\n*.generated.php\n!keep.generated.php\n").unwrap();
+        let matched = |path: &str, is_dir: bool| rules.matched(Path::new(path), is_dir);
+
+        assert_eq!(matched("vendor", true), Some(true));
+        assert_eq!(matched("modules/vendor", true), Some(true));
+        assert_eq!(matched("vendor", false), None);
+        assert_eq!(matched("var/cache", true), Some(true));
+        assert_eq!(matched("src/var/cache", true), None);
+        assert_eq!(matched("src/Entity.generated.php", false), Some(true));
+        assert_eq!(matched("src/keep.generated.php", false), Some(false));
+        assert_eq!(matched("src/vendor.php", false), None);
+    }
+
+    #[test]
+    fn test_filter_counts_exclusions_per_rule() {
+        let dir = write_tree("filter-counts", &[(".gitignore", "Proxies/\n")]);
+        let mut filter = FileFilter::new()
+            .with_include(&["src/**".to_string()])
+            .unwrap()
+            .with_exclude(&["**/Fixtures/**".to_string()])
+            .unwrap();
+
+        let ignores = filter.ignore_stack(&dir).unwrap();
+        let mut accept = |relative: &str| filter.accept(Path::new(relative), Path::new(relative), &ignores);
+
+        assert!(accept("src/User.php"));
+        assert!(!accept("tests/UserTest.php"));
+        assert!(!accept("src/Fixtures/Data.php"));
+        assert!(!accept("src/Fixtures/More.php"));
+        assert!(filter.accept_dir(Path::new("src"), &ignores));
+        assert!(!filter.accept_dir(Path::new("src/Proxies"), &ignores));
+
+        let excluded: Vec<_> = filter.excluded().iter().map(|(k, v)| (k.as_str(), *v)).collect();
+        assert_eq!(excluded, vec![
+            ("not matching --include", 1),
+            ("--exclude **/Fixtures/**", 2),
+            (".gitignore", 1),
+        ]);
+    }
+
+    #[test]
+    fn test_applies_nested_and_parent_ignore_files() {
+        let repository = write_tree("ignore-stack", &[
+            (".git/HEAD", "ref: refs/heads/main\n"),
+            (".gitignore", "app/var/\n"),
+            ("app/.gitignore", "*.local.php\nvendor/\n"),
+            ("app/src/User.php", "<?php"),
+            ("app/src/config.local.php", "<?php"),
+            ("app/src/Legacy/.gitignore", "*.php\n!Kept.php\n"),
+            ("app/src/Legacy/Old.php", "<?php"),
+            ("app/src/Legacy/Kept.php", "<?php"),
+            ("app/var/cache/Proxy.php", "<?php"),
+            ("app/vendor/acme/Lib.php", "<?php"),
+            ("app/.modfatherignore", "!vendor/\n"),
+        ]);
+        let root = repository.join("app");
+        let mut discovery = PhpFileDiscovery::new();
+        discovery.scan_directory(&root).unwrap();
+
+        let mut found: Vec<_> = discovery
+            .get_files()
+            .iter()
+            .map(|path| path.strip_prefix(&root).unwrap().to_string_lossy().into_owned())
+            .collect();
+        found.sort();
+        // `.modfatherignore` is read after `.gitignore`, so it re-includes vendor/
+        assert_eq!(found, vec!["src/Legacy/Kept.php", "src/User.php", "vendor/acme/Lib.php"]);
+
+        let parent = repository.canonicalize().unwrap().join(".gitignore").display().to_string();
+        let mut excluded: Vec<_> = discovery.filter().excluded().iter().map(|(k, v)| (k.clone(), *v)).collect();
+        excluded.sort();
+        let mut expected = vec![
+            (".gitignore".to_string(), 1),
+            (parent, 1),
+            ("src/Legacy/.gitignore".to_string(), 1),
+        ];
+        expected.sort();
+        assert_eq!(excluded, expected);
+    }
+}
@@ -0,0 +1,124 @@
+use crate::model::{CodeModel, Diagnostic, DiagnosticKind};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Lexically normalize a path: drop `.` segments and fold each `..` into the
+/// segment before it. Leading `..` segments of a relative path are kept.
+pub fn normalize_path(path: &str) -> String {
+    let absolute = path.starts_with('/');
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." if segments.last().is_some_and(|last| *last != "..") => {
+                segments.pop();
+            }
+            // `/..` is `/`
+            ".." if absolute => {}
+            _ => segments.push(segment),
+        }
+    }
+
+    let joined = segments.join("/");
+    if absolute {
+        format!("/{}", joined)
+    } else if joined.is_empty() {
+        ".".to_string()
+    } else {
+        joined
+    }
+}
+
+/// Resolve every include to the analyzed file it names, or to a file on disk
+/// outside the analyzed set. Includes built at runtime or naming a file that
+/// does not exist are reported as diagnostics.
+pub fn resolve_includes(model: &mut CodeModel) {
+    let analyzed: HashMap<String, String> = model
+        .files
+        .iter()
+        .map(|file| (normalize_path(&file.path), file.path.clone()))
+        .collect();
+
+    for file in &mut model.files {
+        for include in &mut file.includes {
+            let normalized = include.path.as_deref().map(normalize_path);
+            include.resolved = normalized.as_ref().and_then(|path| {
+                analyzed
+                    .get(path)
+                    .cloned()
+                    .or_else(|| Path::new(path).is_file().then(|| path.clone()))
+            });
+            if include.resolved.is_some() {
+                continue;
+            }
+
+            let message = match &normalized {
+                Some(path) => format!("`{}` names `{}`, which does not exist", include.expression, path),
+                None => format!("`{}` is built at runtime and cannot be resolved", include.expression),
+            };
+            file.diagnostics.push(Diagnostic {
+                kind: DiagnosticKind::UnresolvedInclude,
+                location: include.location.clone(),
+                message,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::extractor::extract_source;
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(normalize_path("./src/../lib/db.php"), "lib/db.php");
+        assert_eq!(normalize_path("src/./a/b/../../c.php"), "src/c.php");
+        assert_eq!(normalize_path("./../shared/x.php"), "../shared/x.php");
+        assert_eq!(normalize_path("/var/www/../../etc"), "/etc");
+        assert_eq!(normalize_path("/.."), "/");
+    }
+
+    #[test]
+    fn test_resolves_static_includes() {
+        let mut model = CodeModel::new();
+        model.add_file(extract_source(
+            "./www/admin/index.php",
+            r#"<?php
+require_once __DIR__ . '/../../lib/db.php';
+include dirname(__FILE__) . "/header.php";
+require dirname(__DIR__, 2) . '/lib/db.php';
+include 'header.php';
+include $theme . '/footer.php';
+require_once '/nonexistent/modfather/config.php';
+"#,
+        ));
+        model.add_file(extract_source("./www/admin/header.php", "<?php\n"));
+        model.add_file(extract_source("lib/db.php", "<?php\n"));
+
+        resolve_includes(&mut model);
+
+        let includes: Vec<_> = model.files[0]
+            .includes
+            .iter()
+            .map(|i| (i.kind.name(), i.resolved.as_deref()))
+            .collect();
+        assert_eq!(includes, vec![
+            ("require_once", Some("lib/db.php")),
+            ("include", Some("./www/admin/header.php")),
+            ("require", Some("lib/db.php")),
+            ("include", Some("./www/admin/header.php")),
+            ("include", None),
+            ("require_once", None),
+        ]);
+
+        let messages: Vec<_> = model
+            .diagnostics()
+            .map(|d| (d.location.line, d.location.column, d.message.as_str()))
+            .collect();
+        assert_eq!(messages, vec![
+            (6, 1, "`include $theme . '/footer.php'` is built at runtime and cannot be resolved"),
+            (7, 1, "`require_once '/nonexistent/modfather/config.php'` names `/nonexistent/modfather/config.php`, which does not exist"),
+        ]);
+    }
+}
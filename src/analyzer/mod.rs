@@ -1,5 +1,16 @@
 pub mod php_parser;
+pub mod extractor;
+pub mod aliases;
+pub mod builtins;
+pub mod cache;
+pub mod composer;
+pub mod diagnostics;
+pub mod docblock;
+pub mod file_filter;
+pub mod includes;
+pub mod psr4;
 
+use file_filter::FileFilter;
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 use anyhow::{Context, Result};
@@ -7,22 +18,45 @@ use anyhow::{Context, Result};
 /// Discovers PHP files in a directory
 pub struct PhpFileDiscovery {
     pub paths: Vec<PathBuf>,
+    filter: FileFilter,
 }
 
 impl PhpFileDiscovery {
     pub fn new() -> Self {
-        Self { paths: Vec::new() }
+        Self {
+            paths: Vec::new(),
+            filter: FileFilter::new(),
+        }
+    }
+
+    pub fn with_filter(mut self, filter: FileFilter) -> Self {
+        self.filter = filter;
+        self
     }
 
-    /// Scan a directory for PHP files
+    /// Scan a directory for PHP files, skipping those rejected by the filter.
+    /// Patterns and ignore files are matched relative to `dir`; ignored
+    /// directories are not walked.
     pub fn scan_directory(&mut self, dir: &Path) -> Result<()> {
-        for entry in WalkDir::new(dir)
-            .follow_links(false)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
+        let mut ignores = self.filter.ignore_stack(dir)?;
+
+        let mut entries = WalkDir::new(dir).follow_links(false).into_iter();
+        while let Some(entry) = entries.next() {
+            let Ok(entry) = entry else {
+                continue;
+            };
             let path = entry.path();
-            if path.is_file() && self.is_php_file(path) {
+            let relative = path.strip_prefix(dir).unwrap_or(path);
+            if entry.file_type().is_dir() {
+                if entry.depth() == 0 {
+                    continue;
+                }
+                if !self.filter.accept_dir(relative, &ignores) {
+                    entries.skip_current_dir();
+                    continue;
+                }
+                ignores.enter_dir(path, relative)?;
+            } else if path.is_file() && self.is_php_file(path) && self.filter.accept(path, relative, &ignores) {
                 self.paths.push(path.to_path_buf());
             }
         }
@@ -41,6 +75,10 @@ impl PhpFileDiscovery {
     pub fn get_files(&self) -> &[PathBuf] {
         &self.paths
     }
+
+    pub fn filter(&self) -> &FileFilter {
+        &self.filter
+    }
 }
 
 /// Read a file's content as a string
@@ -1,24 +1,41 @@
-use anyhow::Result;
+use crate::model::{Diagnostic, DiagnosticKind, Location};
 use bumpalo::Bump;
-use mago_database::file::FileId;
+use mago_database::file::File;
+use mago_span::HasSpan;
 use mago_syntax::ast::Program;
-use mago_syntax::parser::parse_file_content;
-use std::path::Path;
+use mago_syntax::parser::parse_file;
 
-/// Parse a PHP file using Mago and return the AST
+/// Parse a PHP file using Mago and return the AST along with any parse errors.
+/// The AST is partial when diagnostics are returned.
 /// The arena must outlive the returned Program reference
 pub fn parse_php_file<'arena>(
     arena: &'arena Bump,
-    path: &Path,
-    content: &str,
-) -> Result<&'arena Program<'arena>> {
-    let file_id = FileId::zero();
+    file: &File,
+) -> (&'arena Program<'arena>, Vec<Diagnostic>) {
+    let (program, error) = parse_file(arena, file);
 
-    let (program, error) = parse_file_content(arena, file_id, content);
+    let diagnostics = error
+        .map(|err| {
+            let (line, column) = line_and_column(file, err.span().start.offset);
+            vec![Diagnostic {
+                kind: DiagnosticKind::ParseError,
+                location: Location {
+                    file: file.name.to_string(),
+                    line,
+                    column,
+                },
+                message: err.to_string(),
+            }]
+        })
+        .unwrap_or_default();
 
-    if let Some(err) = error {
-        eprintln!("Parse error in {}: {:?}", path.display(), err);
-    }
+    (program, diagnostics)
+}
 
-    Ok(program)
+/// 1-based line and column of a byte offset. `File::column_number` is not used
+/// as it returns the line index for offsets at the very start of a line.
+pub fn line_and_column(file: &File, offset: u32) -> (u32, u32) {
+    let line = file.line_number(offset);
+    let line_start = file.get_line_start_offset(line).unwrap_or(0);
+    (line + 1, offset - line_start + 1)
 }
@@ -0,0 +1,320 @@
+use crate::analyzer::includes::normalize_path;
+use crate::model::{CodeModel, Location, Symbol};
+use serde::Serialize;
+use std::path::Path;
+
+/// A PSR-4 namespace prefix and the directories holding its classes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Psr4Mapping {
+    /// Namespace prefix with a trailing backslash, or empty for a fallback directory
+    pub prefix: String,
+    pub dirs: Vec<String>,
+}
+
+impl Psr4Mapping {
+    pub fn new(prefix: &str, dirs: Vec<String>) -> Self {
+        let prefix = prefix.trim_start_matches('\\').trim_end_matches('\\');
+        Self {
+            prefix: if prefix.is_empty() { String::new() } else { format!("{}\\", prefix) },
+            dirs,
+        }
+    }
+
+    /// Parse a `PREFIX=DIR` command line argument, e.g. `App\=src/`
+    pub fn parse(argument: &str) -> Result<Self, String> {
+        let (prefix, dir) = argument
+            .split_once('=')
+            .ok_or_else(|| format!("expected PREFIX=DIR, got `{}`", argument))?;
+        Ok(Self::new(prefix, vec![dir.to_string()]))
+    }
+
+    /// Path a class under this prefix is expected at, in the first directory
+    fn expected_path(&self, dir: &str, fqn: &str) -> String {
+        let relative = fqn[self.prefix.len()..].replace('\\', "/");
+        normalize_path(&format!("{}/{}.php", dir.trim_end_matches('/'), relative))
+    }
+}
+
+/// What a PSR-4 violation is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Psr4ViolationKind {
+    /// The class is in a different directory than its namespace maps to
+    NamespaceMismatch,
+    /// The file is not named after the class it declares
+    ClassNameMismatch,
+    /// The file declares more than one class-like, so only one can be autoloaded
+    MultipleClasses,
+    /// No prefix of the mapping covers the class's namespace
+    UnmappedNamespace,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Psr4Violation {
+    pub kind: Psr4ViolationKind,
+    pub location: Location,
+    /// Classes the violation is about: every class-like of the file for
+    /// `multiple-classes`, otherwise the one class
+    pub classes: Vec<String>,
+    /// Where PSR-4 expects the class, when a prefix covers it
+    pub expected: Option<String>,
+    pub message: String,
+}
+
+/// Result of checking the analyzed class-likes against a PSR-4 mapping
+#[derive(Debug, Serialize)]
+pub struct Psr4Report {
+    pub files_checked: usize,
+    pub classes_checked: usize,
+    pub violations: Vec<Psr4Violation>,
+}
+
+impl Psr4Report {
+    /// Check every file declaring class-likes: each class must be in the file its
+    /// name maps to under the longest matching prefix (or any other matching
+    /// prefix), and each file must declare a single class-like named after it.
+    /// Relative file paths and mapping directories are both resolved against
+    /// `base`, the working directory, before they are compared.
+    pub fn check(model: &CodeModel, mappings: &[Psr4Mapping], base: &Path) -> Self {
+        let mut report = Self {
+            files_checked: 0,
+            classes_checked: 0,
+            violations: Vec::new(),
+        };
+        let mappings: Vec<Psr4Mapping> = mappings
+            .iter()
+            .map(|mapping| Psr4Mapping {
+                prefix: mapping.prefix.clone(),
+                dirs: mapping.dirs.iter().map(|dir| absolute(base, dir)).collect(),
+            })
+            .collect();
+
+        for file in &model.files {
+            let classes: Vec<&Symbol> = file.symbols.iter().filter(|s| s.kind.is_class_like()).collect();
+            if classes.is_empty() {
+                continue;
+            }
+            report.files_checked += 1;
+            report.classes_checked += classes.len();
+            let path = absolute(base, &file.path);
+            // Expected paths are shown like the file's own path, relative or absolute
+            let shown_base = Path::new(&file.path).is_relative().then_some(base);
+
+            if classes.len() > 1 {
+                let names: Vec<String> = classes.iter().map(|class| class.fqn.clone()).collect();
+                // Reported at the second class-like, the first one the autoloader cannot find
+                report.violations.push(Psr4Violation {
+                    kind: Psr4ViolationKind::MultipleClasses,
+                    location: symbol_location(classes[1]),
+                    message: format!("declares {} class-likes: {}", names.len(), names.join(", ")),
+                    classes: names,
+                    expected: None,
+                });
+            }
+
+            for class in &classes {
+                report.check_class(class, &path, shown_base, &mappings, classes.len() == 1);
+            }
+        }
+
+        report
+    }
+
+    fn check_class(
+        &mut self,
+        class: &Symbol,
+        path: &str,
+        shown_base: Option<&Path>,
+        mappings: &[Psr4Mapping],
+        check_name: bool,
+    ) {
+        let mut matching: Vec<&Psr4Mapping> = mappings
+            .iter()
+            .filter(|mapping| class.fqn.starts_with(&mapping.prefix))
+            .collect();
+        // Longest prefix first, as it names the expected location
+        matching.sort_by_key(|mapping| std::cmp::Reverse(mapping.prefix.len()));
+
+        let candidates: Vec<String> = matching
+            .iter()
+            .flat_map(|mapping| mapping.dirs.iter().map(|dir| mapping.expected_path(dir, &class.fqn)))
+            .collect();
+        let Some(expected) = candidates.first().cloned() else {
+            self.push(class, Psr4ViolationKind::UnmappedNamespace, None, format!(
+                "`{}` is not under any PSR-4 namespace prefix",
+                class.fqn
+            ));
+            return;
+        };
+
+        let directory = |path: &str| Path::new(path).parent().map(Path::to_path_buf);
+        let file_name = |path: &str| Path::new(path).file_name().map(|name| name.to_os_string());
+        let file_matches = candidates.iter().any(|candidate| directory(candidate) == directory(path));
+        let expected = shown(shown_base, &expected);
+        if !file_matches {
+            let shown_path = shown(shown_base, path);
+            self.push(class, Psr4ViolationKind::NamespaceMismatch, Some(expected.clone()), format!(
+                "`{}` is in {}, its namespace maps to {}",
+                class.fqn,
+                directory(&shown_path).unwrap_or_default().display(),
+                directory(&expected).unwrap_or_default().display()
+            ));
+        }
+        if check_name && file_name(&expected) != file_name(path) {
+            let expected_name = file_name(&expected).unwrap_or_default();
+            self.push(class, Psr4ViolationKind::ClassNameMismatch, Some(expected.clone()), format!(
+                "`{}` is declared in {}, expected {}",
+                class.fqn,
+                file_name(path).unwrap_or_default().to_string_lossy(),
+                expected_name.to_string_lossy()
+            ));
+        }
+    }
+
+    fn push(&mut self, class: &Symbol, kind: Psr4ViolationKind, expected: Option<String>, message: String) {
+        self.violations.push(Psr4Violation {
+            kind,
+            location: symbol_location(class),
+            classes: vec![class.fqn.clone()],
+            expected,
+            message,
+        });
+    }
+
+    /// Format the report as human-readable text, grouped by kind of violation
+    pub fn format_text(&self) -> String {
+        let mut output = format!(
+            "PSR-4 check: {} class-likes in {} files, {} violations\n",
+            self.classes_checked,
+            self.files_checked,
+            self.violations.len()
+        );
+
+        for (kind, title) in [
+            (Psr4ViolationKind::NamespaceMismatch, "Namespace does not match the directory"),
+            (Psr4ViolationKind::ClassNameMismatch, "Class name does not match the file name"),
+            (Psr4ViolationKind::MultipleClasses, "Files declaring more than one class-like"),
+            (Psr4ViolationKind::UnmappedNamespace, "Classes outside every namespace prefix"),
+        ] {
+            let violations: Vec<_> = self.violations.iter().filter(|v| v.kind == kind).collect();
+            if violations.is_empty() {
+                continue;
+            }
+            output.push_str(&format!("\n{} ({}):\n", title, violations.len()));
+            for violation in violations {
+                output.push_str(&format!("  {}: {}\n", violation.location, violation.message));
+            }
+        }
+        output
+    }
+
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// `path` resolved against `base` and normalized
+fn absolute(base: &Path, path: &str) -> String {
+    normalize_path(&base.join(path).to_string_lossy())
+}
+
+/// An absolute path as reported: relative to `base` when given and inside it
+fn shown(base: Option<&Path>, path: &str) -> String {
+    let Some(base) = base else {
+        return path.to_string();
+    };
+    let base = normalize_path(&base.to_string_lossy());
+    match path.strip_prefix(base.as_str()) {
+        Some(rest) if rest.starts_with('/') => rest[1..].to_string(),
+        _ => path.to_string(),
+    }
+}
+
+fn symbol_location(symbol: &Symbol) -> Location {
+    Location {
+        file: symbol.file.clone(),
+        line: symbol.line,
+        column: 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::extractor::extract_source;
+
+    #[test]
+    fn test_reports_psr4_violations() {
+        let mut model = CodeModel::new();
+        model.add_file(extract_source("./src/Billing/Invoice.php", "<?php\nnamespace App\\Billing;\nclass Invoice {}\n"));
+        model.add_file(extract_source("src/Billing/Helpers.php", "<?php\nnamespace App\\Billing;\nclass Money {}\n"));
+        model.add_file(extract_source("src/Legacy/Order.php", "<?php\nnamespace App\\Shop;\nclass Order {}\n"));
+        model.add_file(extract_source(
+            "src/Shop/Cart.php",
+            "<?php\nnamespace App\\Shop;\nclass Cart {}\n\ninterface CartItem {}\n",
+        ));
+        model.add_file(extract_source("tests/Shop/CartTest.php", "<?php\nnamespace App\\Tests\\Shop;\nclass CartTest {}\n"));
+        model.add_file(extract_source("lib/Thing.php", "<?php\nclass Legacy_Thing {}\n"));
+        model.add_file(extract_source("public/index.php", "<?php\necho 'hi';\n"));
+
+        let mappings = vec![
+            Psr4Mapping::new("App\\", vec!["src/".to_string()]),
+            Psr4Mapping::parse("\\App\\Tests=tests").unwrap(),
+        ];
+        assert_eq!(mappings[1].prefix, "App\\Tests\\");
+        assert!(Psr4Mapping::parse("App\\").is_err());
+
+        let report = Psr4Report::check(&model, &mappings, Path::new("/work/shop"));
+        assert_eq!((report.files_checked, report.classes_checked), (6, 7));
+
+        let violations: Vec<_> = report
+            .violations
+            .iter()
+            .map(|v| (v.kind, v.location.to_string(), v.expected.as_deref()))
+            .collect();
+        assert_eq!(violations, vec![
+            (Psr4ViolationKind::ClassNameMismatch, "src/Billing/Helpers.php:3:1".to_string(), Some("src/Billing/Money.php")),
+            (Psr4ViolationKind::NamespaceMismatch, "src/Legacy/Order.php:3:1".to_string(), Some("src/Shop/Order.php")),
+            (Psr4ViolationKind::MultipleClasses, "src/Shop/Cart.php:5:1".to_string(), None),
+            (Psr4ViolationKind::UnmappedNamespace, "lib/Thing.php:2:1".to_string(), None),
+        ]);
+
+        assert_eq!(
+            report.format_text(),
+            "PSR-4 check: 7 class-likes in 6 files, 4 violations\n\
+             \n\
+             Namespace does not match the directory (1):\n  \
+             src/Legacy/Order.php:3:1: `App\\Shop\\Order` is in src/Legacy, its namespace maps to src/Shop\n\
+             \n\
+             Class name does not match the file name (1):\n  \
+             src/Billing/Helpers.php:3:1: `App\\Billing\\Money` is declared in Helpers.php, expected Money.php\n\
+             \n\
+             Files declaring more than one class-like (1):\n  \
+             src/Shop/Cart.php:5:1: declares 2 class-likes: App\\Shop\\Cart, App\\Shop\\CartItem\n\
+             \n\
+             Classes outside every namespace prefix (1):\n  \
+             lib/Thing.php:2:1: `Legacy_Thing` is not under any PSR-4 namespace prefix\n"
+        );
+    }
+
+    #[test]
+    fn test_compares_relative_and_absolute_paths() {
+        let mut model = CodeModel::new();
+        model.add_file(extract_source("/work/shop/src/Billing/Invoice.php", "<?php\nnamespace App\\Billing;\nclass Invoice {}\n"));
+        model.add_file(extract_source("/work/shop/src/Legacy/Order.php", "<?php\nnamespace App\\Shop;\nclass Order {}\n"));
+        model.add_file(extract_source("lib/Util.php", "<?php\nnamespace Lib;\nclass Util {}\n"));
+        model.add_file(extract_source("../shop/lib/Text/Slug.php", "<?php\nnamespace Lib\\Text;\nclass Slug {}\n"));
+
+        let mappings = vec![
+            Psr4Mapping::parse("App\\=src").unwrap(),
+            Psr4Mapping::parse("Lib\\=/work/shop/lib/").unwrap(),
+        ];
+        let report = Psr4Report::check(&model, &mappings, Path::new("/work/shop"));
+
+        let violations: Vec<_> = report.violations.iter().map(|v| (v.kind, v.message.as_str())).collect();
+        assert_eq!(violations, vec![(
+            Psr4ViolationKind::NamespaceMismatch,
+            "`App\\Shop\\Order` is in /work/shop/src/Legacy, its namespace maps to /work/shop/src/Shop",
+        )]);
+    }
+}
@@ -14,6 +14,7 @@ impl CsvWriter {
         }
     }
 
+    #[allow(dead_code)]
     pub fn without_header(mut self) -> Self {
         self.include_header = false;
         self
@@ -23,12 +24,26 @@ impl CsvWriter {
     pub fn write<W: Write>(&self, graph: &DependencyGraph, writer: &mut W) -> Result<()> {
         // Write header if requested
         if self.include_header {
-            writeln!(writer, "source,target")?;
+            writeln!(writer, "source,target,kind,weight,methods")?;
         }
 
-        // Write each edge as a row
-        for edge in &graph.edges {
-            writeln!(writer, "{},{}", edge.from, edge.to)?;
+        // Write each edge as a row, sorted for deterministic output
+        let mut sorted_edges: Vec<_> = graph.edges.iter().collect();
+        sorted_edges.sort_by(|a, b| {
+            a.from.cmp(&b.from).then_with(|| a.to.cmp(&b.to))
+        });
+
+        for edge in sorted_edges {
+            let field = |key: &str| edge.metadata.get(key).map(String::as_str).unwrap_or("");
+            writeln!(
+                writer,
+                "{},{},{},{},{}",
+                edge.from,
+                edge.to,
+                field("kind"),
+                field("weight"),
+                field("methods")
+            )?;
         }
 
         Ok(())
@@ -58,7 +73,7 @@ mod tests {
         writer.write(&graph, &mut output).unwrap();
 
         let result = String::from_utf8(output).unwrap();
-        assert_eq!(result, "source,target\nClassA,ClassB\n");
+        assert_eq!(result, "source,target,kind,weight,methods\nClassA,ClassB,,,\n");
     }
 
     #[test]
@@ -73,7 +88,7 @@ mod tests {
         writer.write(&graph, &mut output).unwrap();
 
         let result = String::from_utf8(output).unwrap();
-        assert_eq!(result, "ClassA,ClassB\n");
+        assert_eq!(result, "ClassA,ClassB,,,\n");
     }
 
     #[test]
@@ -82,8 +97,18 @@ mod tests {
         graph.add_node(Node::new("A", "A"));
         graph.add_node(Node::new("B", "B"));
         graph.add_node(Node::new("C", "C"));
-        graph.add_edge(Edge::new("A", "B"));
-        graph.add_edge(Edge::new("A", "C"));
+        graph.add_edge(
+            Edge::new("A", "B")
+                .with_metadata("kind", "extends")
+                .with_metadata("weight", "1")
+                .with_metadata("methods", "0"),
+        );
+        graph.add_edge(
+            Edge::new("A", "C")
+                .with_metadata("kind", "new|param-hint")
+                .with_metadata("weight", "7")
+                .with_metadata("methods", "3"),
+        );
         graph.add_edge(Edge::new("B", "C"));
 
         let writer = CsvWriter::new();
@@ -91,6 +116,6 @@ mod tests {
         writer.write(&graph, &mut output).unwrap();
 
         let result = String::from_utf8(output).unwrap();
-        assert_eq!(result, "source,target\nA,B\nA,C\nB,C\n");
+        assert_eq!(result, "source,target,kind,weight,methods\nA,B,extends,1,0\nA,C,new|param-hint,7,3\nB,C,,,\n");
     }
 }
@@ -1,4 +1,5 @@
 use super::{DependencyGraph, Edge, Node};
+use crate::model::ReferenceKind;
 use std::io::Write;
 
 /// Writes a dependency graph in Graphviz DOT format
@@ -91,8 +92,21 @@ impl DotWriter {
         if is_external {
             write!(writer, ", fillcolor=\"lightyellow\", style=\"rounded,filled,dashed\"")?;
         }
+        let is_builtin = node.metadata.get("type").map(|t| t == "builtin").unwrap_or(false);
+        if is_builtin {
+            write!(writer, ", fillcolor=\"gray90\", style=\"rounded,filled,dotted\"")?;
+        }
+        // Functions and procedural scripts are drawn apart from class-likes
+        match node.metadata.get("kind").map(String::as_str) {
+            Some("function") => write!(writer, ", shape=\"ellipse\"")?,
+            Some("script") => write!(writer, ", shape=\"note\"")?,
+            _ => {}
+        }
+
+        let mut sorted_metadata: Vec<_> = node.metadata.iter().collect();
+        sorted_metadata.sort();
 
-        for (key, value) in &node.metadata {
+        for (key, value) in sorted_metadata {
             // Skip 'type' metadata as it's internal
             if key != "type" {
                 write!(writer, ", {}=\"{}\"", key, self.escape_string(value))?;
@@ -108,19 +122,45 @@ impl DotWriter {
                self.escape_id(&edge.from),
                self.escape_id(&edge.to))?;
 
-        if edge.label.is_some() || !edge.metadata.is_empty() {
+        let style = edge.metadata.get("kind").and_then(|kinds| self.edge_style(kinds));
+        let weight: Option<usize> = edge.metadata.get("weight").and_then(|w| w.parse().ok());
+        // Fall back to the reference count when the edge has no explicit label
+        let label = edge.label.clone().or_else(|| weight.map(|w| w.to_string()));
+
+        if label.is_some() || !edge.metadata.is_empty() {
             write!(writer, " [")?;
             let mut first = true;
 
-            if let Some(label) = &edge.label {
+            if let Some(label) = &label {
                 write!(writer, "label=\"{}\"", self.escape_string(label))?;
                 first = false;
             }
 
-            for (key, value) in &edge.metadata {
+            if let Some(style) = style {
                 if !first {
                     write!(writer, ", ")?;
                 }
+                write!(writer, "{}", style)?;
+                first = false;
+            }
+
+            if let Some(weight) = weight {
+                if !first {
+                    write!(writer, ", ")?;
+                }
+                write!(writer, "penwidth=\"{:.1}\"", self.penwidth(weight))?;
+                first = false;
+            }
+
+            let mut sorted_metadata: Vec<_> = edge.metadata.iter().collect();
+            sorted_metadata.sort();
+
+            for (key, value) in sorted_metadata {
+                if !first {
+                    write!(writer, ", ")?;
+                }
+                // `weight` is a Graphviz layout attribute, so the count is written as `refs`
+                let key = if key == "weight" { "refs" } else { key.as_str() };
                 write!(writer, "{}=\"{}\"", key, self.escape_string(value))?;
                 first = false;
             }
@@ -132,6 +172,36 @@ impl DotWriter {
         Ok(())
     }
 
+    /// Scale line width logarithmically with the reference count so heavy
+    /// dependencies stand out without drowning the rest of the graph
+    fn penwidth(&self, weight: usize) -> f64 {
+        (1.0 + (weight.max(1) as f64).log2()).min(8.0)
+    }
+
+    /// Pick edge attributes from the strongest kind in a `|`-separated kind list
+    fn edge_style(&self, kinds: &str) -> Option<&'static str> {
+        let strongest = kinds.split('|').filter_map(ReferenceKind::from_name).min()?;
+
+        Some(match strongest {
+            ReferenceKind::Extends => "style=\"bold\", arrowhead=\"empty\", color=\"black\"",
+            ReferenceKind::Implements => "style=\"dashed\", arrowhead=\"empty\", color=\"black\"",
+            ReferenceKind::TraitUse => "style=\"dashed\", color=\"purple\"",
+            ReferenceKind::Instantiation => "color=\"darkgreen\"",
+            ReferenceKind::StaticCall => "color=\"blue\"",
+            ReferenceKind::MethodCall => "color=\"dodgerblue\"",
+            ReferenceKind::FunctionCall => "color=\"teal\"",
+            ReferenceKind::StaticProperty | ReferenceKind::ConstantAccess => "color=\"steelblue\"",
+            ReferenceKind::ParameterHint
+            | ReferenceKind::ReturnHint
+            | ReferenceKind::PropertyHint
+            | ReferenceKind::Instanceof => "style=\"dotted\"",
+            ReferenceKind::Catch => "style=\"dotted\", color=\"red\"",
+            ReferenceKind::Attribute => "style=\"dotted\", color=\"orange\"",
+            ReferenceKind::Dynamic => "style=\"dashed\", color=\"goldenrod\"",
+            ReferenceKind::Docblock => "style=\"dotted\", color=\"gray50\"",
+        })
+    }
+
     fn escape_id(&self, s: &str) -> String {
         if s.chars().all(|c| c.is_alphanumeric() || c == '_') && !s.is_empty() {
             s.to_string()
@@ -169,4 +239,36 @@ mod tests {
         assert!(result.contains("B [label=\"Class B\"]"));
         assert!(result.contains("A -> B"));
     }
+
+    #[test]
+    fn test_edges_styled_by_strongest_kind() {
+        let mut graph = DependencyGraph::new();
+        graph.add_edge(Edge::new("A", "B").with_metadata("kind", "extends|param-hint"));
+        graph.add_edge(Edge::new("A", "C").with_metadata("kind", "catch"));
+
+        let writer = DotWriter::new("test");
+        let mut output = Vec::new();
+        writer.write(&graph, &mut output).unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        assert!(result.contains(
+            "A -> B [style=\"bold\", arrowhead=\"empty\", color=\"black\", kind=\"extends|param-hint\"];"
+        ));
+        assert!(result.contains("A -> C [style=\"dotted\", color=\"red\", kind=\"catch\"];"));
+    }
+
+    #[test]
+    fn test_edge_weight_sets_label_and_penwidth() {
+        let mut graph = DependencyGraph::new();
+        graph.add_edge(Edge::new("A", "B").with_metadata("weight", "4"));
+        graph.add_edge(Edge::new("A", "C").with_metadata("weight", "1000"));
+
+        let writer = DotWriter::new("test");
+        let mut output = Vec::new();
+        writer.write(&graph, &mut output).unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        assert!(result.contains("A -> B [label=\"4\", penwidth=\"3.0\", refs=\"4\"];"));
+        assert!(result.contains("A -> C [label=\"1000\", penwidth=\"8.0\", refs=\"1000\"];"));
+    }
 }
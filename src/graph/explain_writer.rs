@@ -0,0 +1,126 @@
+use crate::graph::DependencyGraph;
+use anyhow::Result;
+use std::io::Write;
+
+/// Writes every source location that produces the dependency between two
+/// classes or namespaces
+pub struct ExplainWriter {
+    from: String,
+    to: String,
+}
+
+impl ExplainWriter {
+    pub fn new(from: &str, to: &str) -> Self {
+        Self {
+            from: Self::normalize(from),
+            to: Self::normalize(to),
+        }
+    }
+
+    /// Accept fully qualified names written with a leading backslash
+    fn normalize(name: &str) -> String {
+        match name.strip_prefix('\\') {
+            Some(stripped) if !stripped.is_empty() => stripped.to_string(),
+            _ => name.to_string(),
+        }
+    }
+
+    /// Write the explanation as text, one reference site per line. Names match
+    /// case-insensitively, like PHP class names.
+    pub fn write<W: Write>(&self, graph: &DependencyGraph, writer: &mut W) -> Result<()> {
+        let Some(edge) = graph
+            .edges
+            .iter()
+            .find(|e| e.from.eq_ignore_ascii_case(&self.from) && e.to.eq_ignore_ascii_case(&self.to))
+        else {
+            writeln!(writer, "No dependency from {} to {}", self.from, self.to)?;
+            return Ok(());
+        };
+
+        writeln!(
+            writer,
+            "{} -> {} ({} references)",
+            edge.from,
+            edge.to,
+            edge.references.len()
+        )?;
+
+        // Sort by location for deterministic output
+        let mut references: Vec<_> = edge.references.iter().collect();
+        references.sort_by(|a, b| a.location.cmp(&b.location));
+
+        for reference in references {
+            let source = match &reference.method {
+                Some(method) => format!("{}::{}", reference.from, method),
+                None => reference.from.clone(),
+            };
+            writeln!(
+                writer,
+                "  {}  {}  {} -> {}",
+                reference.location,
+                reference.kind.name(),
+                source,
+                reference.to
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::Edge;
+    use crate::model::{Location, Reference, ReferenceKind};
+
+    fn reference(line: u32, kind: ReferenceKind, method: Option<&str>) -> Reference {
+        Reference {
+            from: "App\\A".to_string(),
+            to: "App\\B".to_string(),
+            kind,
+            method: method.map(str::to_string),
+            location: Location {
+                file: "src/A.php".to_string(),
+                line,
+                column: 5,
+            },
+            unqualified: false,
+        }
+    }
+
+    #[test]
+    fn test_explain_lists_sorted_locations() {
+        let mut edge = Edge::new("App\\A", "App\\B");
+        edge.references = vec![
+            reference(12, ReferenceKind::Instantiation, Some("run")),
+            reference(3, ReferenceKind::Extends, None),
+        ];
+        let mut graph = DependencyGraph::new();
+        graph.add_edge(edge);
+
+        let writer = ExplainWriter::new("\\App\\A", "App\\B");
+        let mut output = Vec::new();
+        writer.write(&graph, &mut output).unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        assert_eq!(
+            result,
+            "App\\A -> App\\B (2 references)\n  \
+             src/A.php:3:5  extends  App\\A -> App\\B\n  \
+             src/A.php:12:5  new  App\\A::run -> App\\B\n"
+        );
+    }
+
+    #[test]
+    fn test_explain_missing_dependency() {
+        let graph = DependencyGraph::new();
+
+        let writer = ExplainWriter::new("App\\A", "App\\B");
+        let mut output = Vec::new();
+        writer.write(&graph, &mut output).unwrap();
+
+        let result = String::from_utf8(output).unwrap();
+        assert_eq!(result, "No dependency from App\\A to App\\B\n");
+    }
+}
@@ -0,0 +1,131 @@
+use crate::graph::{DependencyGraph, Edge, GraphAnalyzer, Node};
+use crate::model::{CodeModel, IncludeKind};
+use indexmap::{IndexMap, IndexSet};
+use std::collections::BTreeSet;
+
+/// How one file includes another
+#[derive(Debug, Default)]
+struct IncludeUsage {
+    kinds: BTreeSet<IncludeKind>,
+    count: usize,
+}
+
+/// Builds a file-level graph of `include`/`require` dependencies from the code
+/// model. Includes must have been resolved with `includes::resolve_includes`.
+pub struct FileDependencyAnalyzer {
+    /// Paths of the analyzed files
+    files: IndexSet<String>,
+    /// Map of file path to the files it includes
+    dependencies: IndexMap<String, IndexMap<String, IncludeUsage>>,
+}
+
+impl FileDependencyAnalyzer {
+    pub fn new() -> Self {
+        Self {
+            files: IndexSet::new(),
+            dependencies: IndexMap::new(),
+        }
+    }
+}
+
+impl GraphAnalyzer for FileDependencyAnalyzer {
+    fn analyze(&mut self, model: &CodeModel) {
+        for file in &model.files {
+            self.files.insert(file.path.clone());
+            for include in &file.includes {
+                let Some(resolved) = &include.resolved else {
+                    continue;
+                };
+                let usage = self
+                    .dependencies
+                    .entry(file.path.clone())
+                    .or_default()
+                    .entry(resolved.clone())
+                    .or_default();
+                usage.kinds.insert(include.kind);
+                usage.count += 1;
+            }
+        }
+    }
+
+    fn build_graph(&self, include_external: bool) -> DependencyGraph {
+        let mut graph = DependencyGraph::new();
+
+        for path in &self.files {
+            graph.add_node(Node::new(path.clone(), path.clone()).with_metadata("type", "internal"));
+        }
+
+        for (from, deps) in &self.dependencies {
+            for (to, usage) in deps {
+                // Files outside the analyzed set, such as vendor or config files
+                let is_external = !self.files.contains(to);
+                if is_external && !include_external {
+                    continue;
+                }
+                if is_external {
+                    graph.add_node(Node::new(to.clone(), to.clone()).with_metadata("type", "external"));
+                }
+
+                let kinds: Vec<_> = usage.kinds.iter().map(|kind| kind.name()).collect();
+                graph.add_edge(
+                    Edge::new(from.clone(), to.clone())
+                        .with_metadata("kind", kinds.join("|"))
+                        .with_metadata("weight", usage.count.to_string()),
+                );
+            }
+        }
+
+        graph
+    }
+}
+
+impl Default for FileDependencyAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{FileModel, Include, Location};
+
+    fn include(kind: IncludeKind, resolved: &str) -> Include {
+        Include {
+            kind,
+            path: Some(resolved.to_string()),
+            expression: String::new(),
+            resolved: Some(resolved.to_string()),
+            location: Location {
+                file: "index.php".to_string(),
+                line: 1,
+                column: 1,
+            },
+        }
+    }
+
+    #[test]
+    fn test_builds_file_graph() {
+        let mut model = CodeModel::new();
+        let mut index = FileModel::new("index.php");
+        index.includes.push(include(IncludeKind::RequireOnce, "lib/db.php"));
+        index.includes.push(include(IncludeKind::Include, "lib/db.php"));
+        index.includes.push(include(IncludeKind::Require, "/etc/app/config.php"));
+        model.add_file(index);
+        model.add_file(FileModel::new("lib/db.php"));
+
+        let mut analyzer = FileDependencyAnalyzer::new();
+        analyzer.analyze(&model);
+
+        let graph = analyzer.build_graph(false);
+        assert_eq!(graph.nodes.len(), 2);
+        assert_eq!(graph.edges.len(), 1);
+        let edge = graph.edges.iter().next().unwrap();
+        assert_eq!((edge.from.as_str(), edge.to.as_str()), ("index.php", "lib/db.php"));
+        assert_eq!(edge.metadata["kind"], "include|require_once");
+        assert_eq!(edge.metadata["weight"], "2");
+
+        let graph = analyzer.build_graph(true);
+        assert_eq!(graph.nodes["/etc/app/config.php"].metadata["type"], "external");
+    }
+}
@@ -0,0 +1,273 @@
+use crate::analyzer::extractor::method_symbol_name;
+use crate::graph::{DependencyGraph, Edge, GraphAnalyzer, Node};
+use crate::model::{CodeModel, ReferenceKind, SymbolKind};
+use indexmap::IndexMap;
+use std::collections::{HashMap, HashSet};
+
+/// Builds a method-level call graph from the code model, with `Class::method`
+/// nodes. Functions and scripts appear as callers.
+pub struct MethodDependencyAnalyzer {
+    /// Map of declared method (and function or script) name to its file path and kind
+    methods: IndexMap<String, (String, &'static str)>,
+    /// Lowercase class name -> lowercase method name -> declared `Class::method`
+    declared: HashMap<String, HashMap<String, String>>,
+    /// Lowercase class name -> the parents and traits it inherits methods from
+    ancestors: HashMap<String, Vec<String>>,
+    /// Map of caller to callees and the number of call sites
+    calls: IndexMap<String, IndexMap<String, usize>>,
+}
+
+impl MethodDependencyAnalyzer {
+    pub fn new() -> Self {
+        Self {
+            methods: IndexMap::new(),
+            declared: HashMap::new(),
+            ancestors: HashMap::new(),
+            calls: IndexMap::new(),
+        }
+    }
+
+    /// The declaration a call to `class::method` reaches: the class's own
+    /// method, or one inherited from a parent class or trait. Calls to methods
+    /// that are not declared anywhere in the analyzed code are kept as written.
+    fn resolve(&self, target: &str) -> String {
+        let Some((class, method)) = target.rsplit_once("::") else {
+            return target.to_string();
+        };
+        let method = method.to_lowercase();
+        let mut pending = vec![class.to_lowercase()];
+        let mut visited = HashSet::new();
+        while let Some(class) = pending.pop() {
+            if !visited.insert(class.clone()) {
+                continue;
+            }
+            if let Some(declaration) = self.declared.get(&class).and_then(|methods| methods.get(&method)) {
+                return declaration.clone();
+            }
+            if let Some(ancestors) = self.ancestors.get(&class) {
+                pending.extend(ancestors.iter().rev().cloned());
+            }
+        }
+        target.to_string()
+    }
+}
+
+impl GraphAnalyzer for MethodDependencyAnalyzer {
+    fn analyze(&mut self, model: &CodeModel) {
+        // A class declared more than once has the methods of every definition, in
+        // the file of the definition the graphs use
+        for (name, symbols) in model.definitions() {
+            let file = &symbols[0].file;
+            match symbols[0].kind {
+                SymbolKind::Function => {
+                    self.methods.insert(name.to_string(), (file.clone(), "function"));
+                }
+                SymbolKind::Script => {
+                    self.methods.insert(name.to_string(), (file.clone(), "script"));
+                }
+                _ => {
+                    let declared = self.declared.entry(name.to_lowercase()).or_default();
+                    for method in symbols.iter().flat_map(|symbol| symbol.methods.iter()) {
+                        let method_name = method_symbol_name(name, method);
+                        if declared.contains_key(&method.to_lowercase()) {
+                            continue;
+                        }
+                        declared.insert(method.to_lowercase(), method_name.clone());
+                        self.methods.insert(method_name, (file.clone(), "method"));
+                    }
+                }
+            }
+        }
+
+        // Parents come from the declarations, as an anonymous class's parent is
+        // not one of the enclosing class
+        for symbol in model.symbols() {
+            if !symbol.parents.is_empty() {
+                self.ancestors
+                    .entry(symbol.fqn.to_lowercase())
+                    .or_default()
+                    .extend(symbol.parents.iter().map(|parent| parent.to_lowercase()));
+            }
+        }
+        for reference in model.references() {
+            if reference.kind == ReferenceKind::TraitUse {
+                self.ancestors
+                    .entry(reference.from.to_lowercase())
+                    .or_default()
+                    .push(reference.to.to_lowercase());
+            }
+        }
+
+        for file in &model.files {
+            for call in &file.calls {
+                let to = self.resolve(&call.to);
+                *self
+                    .calls
+                    .entry(call.from.clone())
+                    .or_default()
+                    .entry(to)
+                    .or_default() += 1;
+            }
+        }
+    }
+
+    fn build_graph(&self, include_external: bool) -> DependencyGraph {
+        let mut graph = DependencyGraph::new();
+
+        for (name, (file_path, kind)) in &self.methods {
+            let mut node = Node::new(name.clone(), name.clone())
+                .with_metadata("file", file_path.clone())
+                .with_metadata("type", "internal");
+            if *kind != "method" {
+                node = node.with_metadata("kind", *kind);
+            }
+            graph.add_node(node);
+        }
+
+        for (from, callees) in &self.calls {
+            for (to, count) in callees {
+                // A method calling itself is recursion, not a dependency
+                if from == to {
+                    continue;
+                }
+                // Methods of classes outside the analyzed code, or magic methods
+                let is_external = !self.methods.contains_key(to);
+                if is_external && !include_external {
+                    continue;
+                }
+                if is_external {
+                    graph.add_node(Node::new(to.clone(), to.clone()).with_metadata("type", "external"));
+                }
+                graph.add_edge(Edge::new(from.clone(), to.clone()).with_metadata("weight", count.to_string()));
+            }
+        }
+
+        graph
+    }
+}
+
+impl Default for MethodDependencyAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::builtins;
+    use crate::analyzer::extractor::extract_source;
+
+    #[test]
+    fn test_builds_method_call_graph() {
+        let mut model = CodeModel::new();
+        model.add_file(extract_source("src/Repository.php", r#"<?php
+namespace App;
+class Repository {
+    public function save(User $user) { $this->flush(); }
+    private function flush() {}
+}
+"#));
+        model.add_file(extract_source("src/Service.php", r#"<?php
+namespace App;
+class Service extends Base {
+    private Repository $repository;
+    public function register(User $user, Mailer $mailer) {
+        $this->repository->save($user);
+        $this->Validate($user);
+        $mailer->send();
+        $mailer->send();
+        $clock = new Clock();
+        $clock->now();
+        $this->register($user);
+        $this->notify();
+    }
+    public function listener() {
+        return new class extends Listener {
+            public function handle() { return Rules::check(); }
+        };
+    }
+    public function fail(Exception $e) {
+        Exception::x();
+        return $e->getMessage();
+    }
+}
+class Listener {
+    public function notify() {}
+}
+class Base {
+    protected function validate(User $user) { return Rules::check($user); }
+}
+"#));
+
+        builtins::apply_fallbacks(&mut model);
+
+        let mut analyzer = MethodDependencyAnalyzer::new();
+        analyzer.analyze(&model);
+
+        let graph = analyzer.build_graph(false);
+        let mut edges: Vec<_> = graph
+            .edges
+            .iter()
+            .map(|e| (e.from.as_str(), e.to.as_str(), e.metadata["weight"].as_str()))
+            .collect();
+        edges.sort();
+        assert_eq!(edges, vec![
+            ("App\\Repository::save", "App\\Repository::flush", "1"),
+            ("App\\Service::register", "App\\Base::validate", "1"),
+            ("App\\Service::register", "App\\Repository::save", "1"),
+        ]);
+
+        let graph = analyzer.build_graph(true);
+        assert_eq!(graph.nodes["App\\Mailer::send"].metadata["type"], "external");
+        assert!(graph.edges.iter().any(|e| e.to == "App\\Mailer::send" && e.metadata["weight"] == "2"));
+        assert!(graph.nodes.contains_key("App\\Clock::now"));
+        assert!(graph.nodes.contains_key("App\\Rules::check"));
+        // The anonymous class's parent is not a parent of the enclosing class
+        assert!(graph.edges.iter().any(|e| e.to == "App\\Service::notify"));
+        // Calls in an anonymous class's methods belong to the enclosing method
+        assert!(graph.edges.iter().any(|e| e.from == "App\\Service::listener" && e.to == "App\\Rules::check"));
+        assert!(graph.edges.iter().all(|e| e.from != "App\\Service::handle"));
+        // Unimported built-ins are not taken for classes of the caller's namespace
+        assert!(graph.nodes.contains_key("Exception::x"));
+        assert!(graph.nodes.contains_key("Exception::getMessage"));
+        assert!(!graph.nodes.keys().any(|n| n.starts_with("App\\Exception")));
+    }
+
+    #[test]
+    fn test_miscased_class_names_join_the_declared_class() {
+        let mut model = CodeModel::new();
+        model.add_file(extract_source("src/Foo.php", r#"<?php
+namespace App;
+class Foo {
+    public static function bar() {}
+    public function baz() {}
+}
+"#));
+        model.add_file(extract_source("src/Client.php", r#"<?php
+namespace App;
+class client {
+    public function run() {
+        foo::bar();
+        (new FOO())->baz();
+        FOO::magic();
+    }
+}
+"#));
+        model.normalize_class_names();
+
+        let mut analyzer = MethodDependencyAnalyzer::new();
+        analyzer.analyze(&model);
+
+        let graph = analyzer.build_graph(true);
+        let mut edges: Vec<_> = graph.edges.iter().map(|e| (e.from.as_str(), e.to.as_str())).collect();
+        edges.sort();
+        assert_eq!(edges, vec![
+            ("App\\client::run", "App\\Foo::bar"),
+            ("App\\client::run", "App\\Foo::baz"),
+            ("App\\client::run", "App\\Foo::magic"),
+        ]);
+        // Undeclared methods are kept as written, but under the declared class
+        assert_eq!(graph.nodes["App\\Foo::magic"].metadata["type"], "external");
+    }
+}
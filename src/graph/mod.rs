@@ -1,10 +1,14 @@
 pub mod class_dependency;
 pub mod namespace_dependency;
+pub mod file_dependency;
+pub mod method_dependency;
 pub mod dot_writer;
 pub mod csv_writer;
+pub mod explain_writer;
 pub mod module_recommender;
 
-use std::collections::{HashMap, HashSet};
+use crate::model::{CodeModel, Package, Reference, ReferenceKind};
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 /// Represents a node in the dependency graph
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -33,6 +37,19 @@ impl Node {
         self.metadata.insert(key.into(), value.into());
         self
     }
+
+    /// Tag the node with the Composer package that owns it and its dev/prod scope.
+    /// External nodes also get the package name in their label.
+    pub fn with_package(mut self, package: Option<&Package>) -> Self {
+        let Some(package) = package else {
+            return self;
+        };
+        if self.metadata.get("type").is_some_and(|t| t == "external") {
+            self.label = format!("{}\n({})", self.label, package.name);
+        }
+        self.with_metadata("package", package.name.clone())
+            .with_metadata("scope", package.scope())
+    }
 }
 
 /// Represents an edge (dependency) in the graph
@@ -42,6 +59,8 @@ pub struct Edge {
     pub to: String,
     pub label: Option<String>,
     pub metadata: HashMap<String, String>,
+    /// Source references that produce this edge, in source order
+    pub references: Vec<Reference>,
 }
 
 impl std::hash::Hash for Edge {
@@ -58,9 +77,11 @@ impl Edge {
             to: to.into(),
             label: None,
             metadata: HashMap::new(),
+            references: Vec::new(),
         }
     }
 
+    #[allow(dead_code)]
     pub fn with_label(mut self, label: impl Into<String>) -> Self {
         self.label = Some(label.into());
         self
@@ -72,6 +93,45 @@ impl Edge {
     }
 }
 
+/// Aggregated usage behind a single dependency edge
+#[derive(Debug, Clone, Default)]
+pub struct DependencyUsage {
+    /// Every kind the dependency is referenced as
+    pub kinds: BTreeSet<ReferenceKind>,
+    /// Every reference site, in source order
+    pub references: Vec<Reference>,
+    /// Distinct `Class::method` pairs the references occur in
+    pub methods: BTreeSet<String>,
+}
+
+impl DependencyUsage {
+    pub fn record(&mut self, reference: &Reference) {
+        self.kinds.insert(reference.kind);
+        self.references.push(reference.clone());
+        if let Some(method) = &reference.method {
+            self.methods.insert(format!("{}::{}", reference.from, method));
+        }
+    }
+
+    /// Fold another usage into this one, e.g. when aggregating classes into namespaces
+    pub fn merge(&mut self, other: &DependencyUsage) {
+        self.kinds.extend(other.kinds.iter().copied());
+        self.references.extend(other.references.iter().cloned());
+        self.methods.extend(other.methods.iter().cloned());
+    }
+
+    /// Build an edge carrying the kinds, reference count (`weight`) and
+    /// distinct-method count (`methods`) as metadata, plus every reference site
+    pub fn to_edge(&self, from: impl Into<String>, to: impl Into<String>) -> Edge {
+        let mut edge = Edge::new(from, to)
+            .with_metadata("kind", ReferenceKind::join(&self.kinds))
+            .with_metadata("weight", self.references.len().to_string())
+            .with_metadata("methods", self.methods.len().to_string());
+        edge.references = self.references.clone();
+        edge
+    }
+}
+
 /// A dependency graph that can be exported to various formats
 #[derive(Debug, Default)]
 pub struct DependencyGraph {
@@ -99,6 +159,7 @@ impl DependencyGraph {
         self.edges.insert(edge);
     }
 
+    #[allow(dead_code)]
     pub fn get_dependencies(&self, node_id: &str) -> Vec<&Node> {
         self.edges
             .iter()
@@ -107,6 +168,7 @@ impl DependencyGraph {
             .collect()
     }
 
+    #[allow(dead_code)]
     pub fn get_dependents(&self, node_id: &str) -> Vec<&Node> {
         self.edges
             .iter()
@@ -116,8 +178,8 @@ impl DependencyGraph {
     }
 }
 
-/// Trait for graph analyzers that extract dependencies from PHP code
+/// Trait for graph analyzers that build dependency graphs from the extracted code model
 pub trait GraphAnalyzer {
-    fn analyze(&mut self, file_path: &str, content: &str) -> anyhow::Result<()>;
+    fn analyze(&mut self, model: &CodeModel);
     fn build_graph(&self, include_external: bool) -> DependencyGraph;
 }
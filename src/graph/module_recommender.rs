@@ -1,7 +1,11 @@
-use crate::graph::DependencyGraph;
-use indexmap::{IndexMap, IndexSet};
+use crate::graph::method_dependency::MethodDependencyAnalyzer;
+use crate::graph::namespace_dependency::NamespaceDependencyAnalyzer;
+use crate::graph::{DependencyGraph, GraphAnalyzer};
+use crate::model::CodeModel;
+use indexmap::IndexMap;
 use petgraph::algo::tarjan_scc;
 use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::visit::EdgeRef;
 use std::collections::{HashMap, HashSet};
 
 /// Represents a suggested module grouping
@@ -32,26 +36,39 @@ pub enum CycleType {
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum CycleSeverity {
-    Low,     // Few edges in cycle
-    Medium,  // Multiple edges
-    High,    // Many edges, tightly coupled
+    Low,     // Few references in cycle
+    Medium,  // Multiple references
+    High,    // Many references, tightly coupled
 }
 
 /// Recommendation for breaking a cycle
 #[derive(Debug, Clone)]
 pub struct CycleBreakingRecommendation {
+    #[allow(dead_code)]
     pub cycle: CycleDetection,
     pub suggestions: Vec<String>,
     pub impact: String,
 }
 
+/// A class whose methods fall into groups that never call each other, a
+/// candidate for splitting along the seam between them
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassSeam {
+    pub class: String,
+    /// Method names of each group, sorted
+    pub groups: Vec<Vec<String>>,
+}
+
 /// Analyzes a dependency graph and recommends module structure
 pub struct ModuleRecommender {
-    namespace_graph: DiGraph<String, ()>,
+    /// Namespace graph weighted by the number of class references behind each edge
+    namespace_graph: DiGraph<String, usize>,
     namespace_to_index: HashMap<String, NodeIndex>,
-    namespace_metrics: HashMap<String, NamespaceMetrics>,
+    namespace_metrics: IndexMap<String, NamespaceMetrics>,
     min_module_size: usize,
     max_module_size: usize,
+    /// Seams found in the method call graph, when built from the code model
+    class_seams: Vec<ClassSeam>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -67,10 +84,14 @@ impl ModuleRecommender {
     pub fn new(graph: &DependencyGraph) -> Self {
         let mut namespace_graph = DiGraph::new();
         let mut namespace_to_index = HashMap::new();
-        let mut namespace_metrics: HashMap<String, NamespaceMetrics> = HashMap::new();
+        let mut namespace_metrics: IndexMap<String, NamespaceMetrics> = IndexMap::new();
 
-        // Create nodes for each namespace
-        for (ns_id, node) in &graph.nodes {
+        // Create nodes for each namespace, in sorted order so cycles and
+        // suggestions come out the same on every run
+        let mut sorted_nodes: Vec<_> = graph.nodes.iter().collect();
+        sorted_nodes.sort_by(|a, b| a.0.cmp(b.0));
+
+        for (ns_id, node) in sorted_nodes {
             let idx = namespace_graph.add_node(ns_id.clone());
             namespace_to_index.insert(ns_id.clone(), idx);
 
@@ -87,13 +108,19 @@ impl ModuleRecommender {
             });
         }
 
-        // Add edges
-        for edge in &graph.edges {
+        // Add edges, weighted by reference count (edges without a weight count once)
+        let mut sorted_edges: Vec<_> = graph.edges.iter().collect();
+        sorted_edges.sort_by(|a, b| a.from.cmp(&b.from).then_with(|| a.to.cmp(&b.to)));
+
+        for edge in sorted_edges {
             if let (Some(&from_idx), Some(&to_idx)) = (
                 namespace_to_index.get(&edge.from),
                 namespace_to_index.get(&edge.to),
             ) {
-                namespace_graph.add_edge(from_idx, to_idx, ());
+                let weight = edge.metadata.get("weight")
+                    .and_then(|w| w.parse().ok())
+                    .unwrap_or(1);
+                namespace_graph.add_edge(from_idx, to_idx, weight);
 
                 // Update metrics
                 if let Some(metrics) = namespace_metrics.get_mut(&edge.from) {
@@ -111,7 +138,36 @@ impl ModuleRecommender {
             namespace_metrics,
             min_module_size: 5,
             max_module_size: 100,
+            class_seams: Vec::new(),
+        }
+    }
+
+    /// Create a new recommender from the code model, counting the classes
+    /// declared in each namespace rather than the files, and looking for seams
+    /// within classes in the method call graph
+    pub fn from_model(model: &CodeModel) -> Self {
+        // Build namespace dependency graph (without external dependencies for cleaner analysis)
+        let mut analyzer = NamespaceDependencyAnalyzer::new();
+        analyzer.analyze(model);
+        let graph = analyzer.build_graph(false);
+
+        let mut recommender = Self::new(&graph);
+        for symbol in model.symbols().filter(|s| s.kind.is_class_like()) {
+            let namespace = symbol.namespace.as_deref().unwrap_or("\\");
+            if let Some(metrics) = recommender.namespace_metrics.get_mut(namespace) {
+                metrics.classes.insert(symbol.fqn.clone());
+            }
         }
+        // Namespaces of only functions and scripts declare no classes
+        for metrics in recommender.namespace_metrics.values_mut() {
+            metrics.class_count = metrics.classes.len();
+        }
+
+        let mut methods = MethodDependencyAnalyzer::new();
+        methods.analyze(model);
+        recommender.class_seams = detect_class_seams(&methods.build_graph(false));
+
+        recommender
     }
 
     /// Set module size constraints
@@ -140,11 +196,11 @@ impl ModuleRecommender {
                     _ => CycleType::Complex,
                 };
 
-                // Calculate severity based on number of edges in cycle
-                let edge_count = self.count_edges_in_cycle(&scc);
-                let severity = match edge_count {
-                    0..=2 => CycleSeverity::Low,
-                    3..=5 => CycleSeverity::Medium,
+                // Calculate severity based on the references holding the cycle together
+                let reference_count = self.count_references_in_cycle(&scc);
+                let severity = match reference_count {
+                    0..=5 => CycleSeverity::Low,
+                    6..=20 => CycleSeverity::Medium,
                     _ => CycleSeverity::High,
                 };
 
@@ -171,15 +227,15 @@ impl ModuleRecommender {
         cycles
     }
 
-    /// Count edges within a strongly connected component
-    fn count_edges_in_cycle(&self, scc: &[NodeIndex]) -> usize {
+    /// Sum the reference counts of the edges within a strongly connected component
+    fn count_references_in_cycle(&self, scc: &[NodeIndex]) -> usize {
         let scc_set: HashSet<_> = scc.iter().copied().collect();
         let mut count = 0;
 
         for &node in scc {
-            for neighbor in self.namespace_graph.neighbors(node) {
-                if scc_set.contains(&neighbor) {
-                    count += 1;
+            for edge in self.namespace_graph.edges(node) {
+                if scc_set.contains(&edge.target()) {
+                    count += edge.weight();
                 }
             }
         }
@@ -270,7 +326,7 @@ impl ModuleRecommender {
         // Group namespaces by their top-level prefix
         let mut namespace_groups: IndexMap<String, Vec<String>> = IndexMap::new();
 
-        for (namespace, _metrics) in &self.namespace_metrics {
+        for namespace in self.namespace_metrics.keys() {
             // Skip the global namespace
             if namespace == "\\" {
                 continue;
@@ -285,7 +341,7 @@ impl ModuleRecommender {
 
             namespace_groups
                 .entry(top_level)
-                .or_insert_with(Vec::new)
+                .or_default()
                 .push(namespace.clone());
         }
 
@@ -365,7 +421,7 @@ impl ModuleRecommender {
 
             second_level_groups
                 .entry(second_level)
-                .or_insert_with(Vec::new)
+                .or_default()
                 .push(namespace.clone());
         }
 
@@ -427,7 +483,7 @@ impl ModuleRecommender {
         split_suggestions
     }
 
-    /// Calculate internal vs external dependencies for a group of namespaces
+    /// Calculate internal vs external references for a group of namespaces
     fn calculate_module_dependencies(&self, namespaces: &[String]) -> (usize, usize) {
         let namespace_set: HashSet<_> = namespaces.iter().collect();
         let mut internal = 0;
@@ -435,12 +491,12 @@ impl ModuleRecommender {
 
         for namespace in namespaces {
             if let Some(&idx) = self.namespace_to_index.get(namespace) {
-                for neighbor in self.namespace_graph.neighbors(idx) {
-                    if let Some(neighbor_ns) = self.namespace_graph.node_weight(neighbor) {
+                for edge in self.namespace_graph.edges(idx) {
+                    if let Some(neighbor_ns) = self.namespace_graph.node_weight(edge.target()) {
                         if namespace_set.contains(neighbor_ns) {
-                            internal += 1;
+                            internal += edge.weight();
                         } else {
-                            external += 1;
+                            external += edge.weight();
                         }
                     }
                 }
@@ -469,10 +525,74 @@ impl ModuleRecommender {
             cycles,
             cycle_breaking_recommendations: recommendations,
             module_suggestions,
+            class_seams: self.class_seams.clone(),
         }
     }
 }
 
+/// Find classes whose methods split into two or more groups that call within
+/// the group but never across groups. Methods with no calls to or from their
+/// own class, such as accessors, belong to no group.
+pub fn detect_class_seams(method_graph: &DependencyGraph) -> Vec<ClassSeam> {
+    // Union-find over the methods of each class, joined by calls within the class
+    let mut parent: HashMap<&str, &str> = HashMap::new();
+    fn find<'g>(parent: &mut HashMap<&'g str, &'g str>, node: &'g str) -> &'g str {
+        let mut root = node;
+        while let Some(&next) = parent.get(root) {
+            if next == root {
+                break;
+            }
+            root = next;
+        }
+        parent.insert(node, root);
+        root
+    }
+
+    for edge in &method_graph.edges {
+        let (Some((from_class, _)), Some((to_class, _))) = (edge.from.rsplit_once("::"), edge.to.rsplit_once("::"))
+        else {
+            continue;
+        };
+        if from_class != to_class {
+            continue;
+        }
+        parent.entry(edge.from.as_str()).or_insert(edge.from.as_str());
+        parent.entry(edge.to.as_str()).or_insert(edge.to.as_str());
+        let from_root = find(&mut parent, &edge.from);
+        let to_root = find(&mut parent, &edge.to);
+        parent.insert(from_root, to_root);
+    }
+
+    let mut groups: IndexMap<&str, IndexMap<&str, Vec<String>>> = IndexMap::new();
+    let mut methods: Vec<&str> = parent.keys().copied().collect();
+    methods.sort();
+    for method in methods {
+        let root = find(&mut parent, method);
+        let (class, name) = method.rsplit_once("::").unwrap_or((method, method));
+        groups
+            .entry(class)
+            .or_default()
+            .entry(root)
+            .or_default()
+            .push(name.to_string());
+    }
+
+    let mut seams: Vec<ClassSeam> = groups
+        .into_iter()
+        .filter(|(_, class_groups)| class_groups.len() > 1)
+        .map(|(class, class_groups)| {
+            let mut groups: Vec<Vec<String>> = class_groups.into_values().collect();
+            groups.sort();
+            ClassSeam {
+                class: class.to_string(),
+                groups,
+            }
+        })
+        .collect();
+    seams.sort_by(|a, b| a.class.cmp(&b.class));
+    seams
+}
+
 /// Complete report of modularization analysis
 #[derive(Debug)]
 pub struct ModularizationReport {
@@ -481,6 +601,7 @@ pub struct ModularizationReport {
     pub cycles: Vec<CycleDetection>,
     pub cycle_breaking_recommendations: Vec<CycleBreakingRecommendation>,
     pub module_suggestions: Vec<ModuleSuggestion>,
+    pub class_seams: Vec<ClassSeam>,
 }
 
 impl ModularizationReport {
@@ -517,7 +638,7 @@ impl ModularizationReport {
                 for ns in &cycle.namespaces {
                     output.push_str(&format!("- `{}`\n", ns));
                 }
-                output.push_str("\n");
+                output.push('\n');
             }
 
             // Recommendations
@@ -531,7 +652,7 @@ impl ModularizationReport {
                 for suggestion in &rec.suggestions {
                     output.push_str(&format!("- {}\n", suggestion));
                 }
-                output.push_str("\n");
+                output.push('\n');
             }
         } else {
             output.push_str("## ✅ No Circular Dependencies\n\n");
@@ -546,16 +667,97 @@ impl ModularizationReport {
             output.push_str(&format!("### {}. {}\n\n", i + 1, module.name));
             output.push_str(&format!("- **Classes**: {}\n", module.class_count));
             output.push_str(&format!("- **Cohesion Score**: {:.2} (higher is better)\n", module.cohesion_score));
-            output.push_str(&format!("- **Internal Dependencies**: {}\n", module.internal_dependencies));
-            output.push_str(&format!("- **External Dependencies**: {}\n", module.external_dependencies));
+            output.push_str(&format!("- **Internal References**: {}\n", module.internal_dependencies));
+            output.push_str(&format!("- **External References**: {}\n", module.external_dependencies));
             output.push_str("\n**Namespaces**:\n");
 
             for ns in &module.namespaces {
                 output.push_str(&format!("- `{}`\n", ns));
             }
-            output.push_str("\n");
+            output.push('\n');
+        }
+
+        // Class Seams
+        if !self.class_seams.is_empty() {
+            output.push_str("## ✂️ Class Seams\n\n");
+            output.push_str("The methods of these classes form groups that never call each other. Each group is a candidate for extraction into its own class or service.\n\n");
+
+            for seam in &self.class_seams {
+                output.push_str(&format!("### `{}`\n\n", seam.class));
+                for (i, group) in seam.groups.iter().enumerate() {
+                    let methods: Vec<_> = group.iter().map(|m| format!("`{}`", m)).collect();
+                    output.push_str(&format!("- Group {}: {}\n", i + 1, methods.join(", ")));
+                }
+                output.push('\n');
+            }
         }
 
         output
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::Edge;
+
+    fn cycle_graph(weight_ab: usize, weight_ba: usize) -> DependencyGraph {
+        let mut graph = DependencyGraph::new();
+        graph.add_edge(Edge::new("App\\A", "App\\B").with_metadata("weight", weight_ab.to_string()));
+        graph.add_edge(Edge::new("App\\B", "App\\A").with_metadata("weight", weight_ba.to_string()));
+        graph
+    }
+
+    #[test]
+    fn test_cycle_severity_uses_reference_counts() {
+        let light = ModuleRecommender::new(&cycle_graph(1, 2)).detect_cycles();
+        assert_eq!(light[0].severity, CycleSeverity::Low);
+
+        let heavy = ModuleRecommender::new(&cycle_graph(200, 3)).detect_cycles();
+        assert_eq!(heavy[0].severity, CycleSeverity::High);
+    }
+
+    #[test]
+    fn test_detects_class_seams() {
+        let mut graph = DependencyGraph::new();
+        graph.add_edge(Edge::new("App\\Shop::checkout", "App\\Shop::charge"));
+        graph.add_edge(Edge::new("App\\Shop::charge", "App\\Shop::receipt"));
+        graph.add_edge(Edge::new("App\\Shop::search", "App\\Shop::filter"));
+        graph.add_edge(Edge::new("App\\Shop::search", "App\\Index::query"));
+        graph.add_edge(Edge::new("App\\Index::query", "App\\Index::tokenize"));
+
+        assert_eq!(detect_class_seams(&graph), vec![ClassSeam {
+            class: "App\\Shop".to_string(),
+            groups: vec![
+                vec!["charge".to_string(), "checkout".to_string(), "receipt".to_string()],
+                vec!["filter".to_string(), "search".to_string()],
+            ],
+        }]);
+    }
+
+    #[test]
+    fn test_module_dependencies_are_weighted() {
+        let mut graph = cycle_graph(4, 1);
+        graph.add_edge(Edge::new("App\\A", "Lib\\C").with_metadata("weight", "5"));
+
+        let recommender = ModuleRecommender::new(&graph);
+        let namespaces = vec!["App\\A".to_string(), "App\\B".to_string()];
+        assert_eq!(recommender.calculate_module_dependencies(&namespaces), (5, 5));
+    }
+
+    #[test]
+    fn test_function_only_namespaces_have_no_classes() {
+        use crate::analyzer::extractor::extract_source;
+
+        let mut model = CodeModel::new();
+        model.add_file(extract_source("src/Models/User.php", "<?php\nnamespace App\\Models;\nclass User {}\nclass Order {}\n"));
+        model.add_file(extract_source(
+            "src/Support/helpers.php",
+            "<?php\nnamespace App\\Support;\nfunction user() { return new \\App\\Models\\User(); }\nfunction order() { return new \\App\\Models\\Order(); }\n",
+        ));
+
+        let suggestions = ModuleRecommender::from_model(&model).suggest_modules();
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].class_count, 2);
+    }
+}
@@ -1,11 +1,8 @@
-use crate::analyzer::php_parser::parse_php_file;
-use crate::graph::{DependencyGraph, Edge, GraphAnalyzer, Node};
-use anyhow::Result;
-use bumpalo::Bump;
+use crate::analyzer::builtins;
+use crate::graph::{DependencyGraph, DependencyUsage, GraphAnalyzer, Node};
+use crate::model::{pear_namespace, CodeModel, PackageIndex, Reference};
 use indexmap::IndexMap;
-use mago_syntax::ast::*;
 use std::collections::{HashMap, HashSet};
-use std::path::Path;
 
 /// Extract namespace from a fully qualified class name
 fn extract_namespace_from_class(class_fqn: &str) -> String {
@@ -16,45 +13,22 @@ fn extract_namespace_from_class(class_fqn: &str) -> String {
     }
 }
 
-/// Tracks imported classes via `use` statements
-#[derive(Debug, Default, Clone)]
-struct ImportContext {
-    /// Map of short name -> fully qualified name
-    imports: HashMap<String, String>,
-}
-
-impl ImportContext {
-    fn new() -> Self {
-        Self::default()
-    }
-
-    fn add_import(&mut self, fully_qualified: String, alias: Option<String>) {
-        let short_name = if let Some(alias) = alias {
-            alias
-        } else {
-            // Extract the last part of the FQN as the short name
-            fully_qualified
-                .split('\\')
-                .last()
-                .unwrap_or(&fully_qualified)
-                .to_string()
-        };
-        self.imports.insert(short_name, fully_qualified);
-    }
-
-    fn resolve(&self, name: &str) -> Option<&String> {
-        self.imports.get(name)
-    }
-}
+/// Pseudo-namespace that PHP built-in classes are grouped under
+const BUILTIN_NAMESPACE: &str = "<builtin>";
 
-/// Extracts namespace dependencies from PHP code
+/// Builds a namespace-level dependency graph from the code model
 pub struct NamespaceDependencyAnalyzer {
     /// Map of class FQN to its namespace
     class_to_namespace: HashMap<String, String>,
     /// Map of namespace to its file paths
     namespace_files: IndexMap<String, HashSet<String>>,
-    /// Map of class to its dependencies (for building namespace deps)
-    class_dependencies: HashMap<String, HashSet<String>>,
+    /// Map of class to its dependencies and how each one is used (for building namespace deps),
+    /// in model order so merged namespace edges list their references in source order
+    class_dependencies: IndexMap<String, IndexMap<String, DependencyUsage>>,
+    /// Composer packages used to tag nodes
+    packages: PackageIndex,
+    /// Place external PEAR-style class names in their pseudo-namespace too
+    pear_namespaces: bool,
 }
 
 impl NamespaceDependencyAnalyzer {
@@ -62,329 +36,57 @@ impl NamespaceDependencyAnalyzer {
         Self {
             class_to_namespace: HashMap::new(),
             namespace_files: IndexMap::new(),
-            class_dependencies: HashMap::new(),
-        }
-    }
-
-    /// Visit the AST and extract class and namespace information
-    fn visit_program(&mut self, program: &Program, file_path: &str, namespace: Option<String>) {
-        let mut imports = ImportContext::new();
-        for statement in program.statements.iter() {
-            self.visit_statement(statement, file_path, namespace.as_deref(), &mut imports);
-        }
-    }
-
-    fn visit_statement(&mut self, statement: &Statement, file_path: &str, namespace: Option<&str>, imports: &mut ImportContext) {
-        match statement {
-            Statement::Use(use_stmt) => {
-                self.process_use_statement(use_stmt, imports);
-            }
-            Statement::Namespace(ns) => {
-                let ns_name = self.extract_namespace_name(ns);
-                // Namespace gets its own import context
-                let mut ns_imports = ImportContext::new();
-
-                for stmt in ns.statements().iter() {
-                    self.visit_statement(stmt, file_path, Some(&ns_name), &mut ns_imports);
-                }
-            }
-            Statement::Class(class) => {
-                self.process_class(class, file_path, namespace, imports);
-            }
-            Statement::Interface(interface) => {
-                self.process_interface(interface, file_path, namespace, imports);
-            }
-            Statement::Trait(trait_def) => {
-                self.process_trait(trait_def, file_path, namespace, imports);
-            }
-            Statement::Enum(enum_def) => {
-                self.process_enum(enum_def, file_path, namespace, imports);
-            }
-            _ => {}
+            class_dependencies: IndexMap::new(),
+            packages: PackageIndex::default(),
+            pear_namespaces: false,
         }
     }
 
-    fn process_use_statement(&mut self, use_stmt: &Use, imports: &mut ImportContext) {
-        // Handle the items in the use statement
-        match &use_stmt.items {
-            UseItems::Sequence(seq) => {
-                for item in seq.items.iter() {
-                    let fqn = match &item.name {
-                        Identifier::Qualified(q) => q.value.to_string(),
-                        Identifier::FullyQualified(f) => f.value.to_string(),
-                        Identifier::Local(l) => l.value.to_string(),
-                    };
-
-                    let alias = item.alias.as_ref().map(|a| a.identifier.value.to_string());
-                    imports.add_import(fqn, alias);
-                }
-            }
-            _ => {} // Handle other use statement types if needed
-        }
-    }
-
-    fn process_class(&mut self, class: &Class, file_path: &str, namespace: Option<&str>, imports: &ImportContext) {
-        let class_name = &class.name.value;
-        let fqn = self.get_fqn(class_name, namespace);
-        let ns = namespace.unwrap_or("\\").to_string();
-
-        self.class_to_namespace.insert(fqn.clone(), ns.clone());
-        self.namespace_files
-            .entry(ns)
-            .or_insert_with(HashSet::new)
-            .insert(file_path.to_string());
-
-        // Analyze parent class
-        if let Some(ref extends) = class.extends {
-            for parent in extends.types.iter() {
-                let parent_name = self.extract_identifier_from_name(parent);
-                let parent_fqn = self.resolve_class_name(&parent_name, namespace, imports);
-                self.add_class_dependency(&fqn, &parent_fqn);
-            }
-        }
-
-        // Analyze interfaces
-        if let Some(ref implements) = class.implements {
-            for interface in implements.types.iter() {
-                let interface_name = self.extract_identifier_from_name(interface);
-                let interface_fqn = self.resolve_class_name(&interface_name, namespace, imports);
-                self.add_class_dependency(&fqn, &interface_fqn);
-            }
-        }
-
-        // Visit class members
-        for member in class.members.iter() {
-            self.visit_class_member(member, &fqn, namespace, imports);
-        }
-    }
-
-    fn process_interface(&mut self, interface: &Interface, file_path: &str, namespace: Option<&str>, imports: &ImportContext) {
-        let interface_name = &interface.name.value;
-        let fqn = self.get_fqn(interface_name, namespace);
-        let ns = namespace.unwrap_or("\\").to_string();
-
-        self.class_to_namespace.insert(fqn.clone(), ns.clone());
-        self.namespace_files
-            .entry(ns)
-            .or_insert_with(HashSet::new)
-            .insert(file_path.to_string());
-
-        // Analyze parent interfaces
-        if let Some(ref extends) = interface.extends {
-            for parent in extends.types.iter() {
-                let parent_name = self.extract_identifier_from_name(parent);
-                let parent_fqn = self.resolve_class_name(&parent_name, namespace, imports);
-                self.add_class_dependency(&fqn, &parent_fqn);
-            }
-        }
-    }
-
-    fn process_trait(&mut self, trait_def: &Trait, file_path: &str, namespace: Option<&str>, imports: &ImportContext) {
-        let trait_name = &trait_def.name.value;
-        let fqn = self.get_fqn(trait_name, namespace);
-        let ns = namespace.unwrap_or("\\").to_string();
-
-        self.class_to_namespace.insert(fqn.clone(), ns.clone());
-        self.namespace_files
-            .entry(ns)
-            .or_insert_with(HashSet::new)
-            .insert(file_path.to_string());
-
-        // Visit trait members
-        for member in trait_def.members.iter() {
-            self.visit_class_member(member, &fqn, namespace, imports);
-        }
-    }
-
-    fn process_enum(&mut self, enum_def: &Enum, file_path: &str, namespace: Option<&str>, imports: &ImportContext) {
-        let enum_name = &enum_def.name.value;
-        let fqn = self.get_fqn(enum_name, namespace);
-        let ns = namespace.unwrap_or("\\").to_string();
-
-        self.class_to_namespace.insert(fqn.clone(), ns.clone());
-        self.namespace_files
-            .entry(ns)
-            .or_insert_with(HashSet::new)
-            .insert(file_path.to_string());
-
-        // Analyze backing type hint
-        if let Some(ref backing) = enum_def.backing_type_hint {
-            self.extract_backing_type_dependencies(backing, &fqn, namespace, imports);
-        }
-
-        // Analyze interfaces
-        if let Some(ref implements) = enum_def.implements {
-            for interface in implements.types.iter() {
-                let interface_name = self.extract_identifier_from_name(interface);
-                let interface_fqn = self.resolve_class_name(&interface_name, namespace, imports);
-                self.add_class_dependency(&fqn, &interface_fqn);
-            }
-        }
-    }
-
-    fn visit_class_member(&mut self, member: &ClassLikeMember, current_class: &str, namespace: Option<&str>, imports: &ImportContext) {
-        match member {
-            ClassLikeMember::TraitUse(trait_use) => {
-                for trait_name in trait_use.trait_names.iter() {
-                    let trait_fqn_str = self.extract_identifier_from_name(trait_name);
-                    let trait_fqn = self.resolve_class_name(&trait_fqn_str, namespace, imports);
-                    self.add_class_dependency(current_class, &trait_fqn);
-                }
-            }
-            ClassLikeMember::Property(property) => {
-                match property {
-                    Property::Plain(plain) => {
-                        if let Some(ref hint) = plain.hint {
-                            self.extract_hint_dependencies(hint, current_class, namespace, imports);
-                        }
-                    }
-                    Property::Hooked(hooked) => {
-                        if let Some(ref hint) = hooked.hint {
-                            self.extract_hint_dependencies(hint, current_class, namespace, imports);
-                        }
-                    }
-                }
-            }
-            ClassLikeMember::Method(method) => {
-                // Check return type
-                if let Some(ref return_type) = method.return_type_hint {
-                    self.extract_return_type_dependencies(return_type, current_class, namespace, imports);
-                }
-
-                // Check parameter types
-                for param in method.parameter_list.parameters.iter() {
-                    if let Some(ref hint) = param.hint {
-                        self.extract_hint_dependencies(hint, current_class, namespace, imports);
-                    }
-                }
-            }
-            _ => {}
-        }
-    }
-
-    fn extract_hint_dependencies(&mut self, hint: &Hint, current_class: &str, namespace: Option<&str>, imports: &ImportContext) {
-        match hint {
-            Hint::Identifier(id) => {
-                let type_name = id.value();
-                if self.is_class_type(type_name) {
-                    let type_fqn = self.resolve_class_name(type_name, namespace, imports);
-                    self.add_class_dependency(current_class, &type_fqn);
-                }
-            }
-            Hint::Parenthesized(p) => {
-                self.extract_hint_dependencies(&p.hint, current_class, namespace, imports);
-            }
-            Hint::Nullable(n) => {
-                self.extract_hint_dependencies(&n.hint, current_class, namespace, imports);
-            }
-            Hint::Union(u) => {
-                self.extract_hint_dependencies(&u.left, current_class, namespace, imports);
-                self.extract_hint_dependencies(&u.right, current_class, namespace, imports);
-            }
-            Hint::Intersection(i) => {
-                self.extract_hint_dependencies(&i.left, current_class, namespace, imports);
-                self.extract_hint_dependencies(&i.right, current_class, namespace, imports);
-            }
-            _ => {}
-        }
-    }
-
-    fn extract_return_type_dependencies(&mut self, return_type: &FunctionLikeReturnTypeHint, current_class: &str, namespace: Option<&str>, imports: &ImportContext) {
-        self.extract_hint_dependencies(&return_type.hint, current_class, namespace, imports);
+    fn add_class_dependency(&mut self, reference: &Reference) {
+        self.class_dependencies
+            .entry(reference.from.clone())
+            .or_default()
+            .entry(reference.to.clone())
+            .or_default()
+            .record(reference);
     }
+}
 
-    fn extract_backing_type_dependencies(&mut self, backing: &EnumBackingTypeHint, current_class: &str, namespace: Option<&str>, imports: &ImportContext) {
-        self.extract_hint_dependencies(&backing.hint, current_class, namespace, imports);
-    }
+impl GraphAnalyzer for NamespaceDependencyAnalyzer {
+    fn analyze(&mut self, model: &CodeModel) {
+        self.packages = model.packages.clone();
+        self.pear_namespaces = model.pear_namespaces;
 
-    fn extract_namespace_name(&self, ns: &Namespace) -> String {
-        if let Some(name) = &ns.name {
-            match name {
-                Identifier::Qualified(q) => q.value.to_string(),
-                Identifier::Local(l) => l.value.to_string(),
-                _ => String::new(),
-            }
-        } else {
-            String::new()
-        }
-    }
+        for symbol in model.symbols() {
+            let ns = symbol.namespace.clone().unwrap_or_else(|| "\\".to_string());
 
-    fn extract_identifier_from_name(&self, name: &Identifier) -> String {
-        match name {
-            Identifier::Qualified(q) => q.value.to_string(),
-            Identifier::Local(l) => l.value.to_string(),
-            Identifier::FullyQualified(f) => f.value.to_string(),
+            self.class_to_namespace.insert(symbol.fqn.clone(), ns.clone());
+            self.namespace_files
+                .entry(ns)
+                .or_default()
+                .insert(symbol.file.clone());
         }
-    }
 
-    fn get_fqn(&self, name: &str, namespace: Option<&str>) -> String {
-        if name.starts_with('\\') {
-            name.to_string()
-        } else if let Some(ns) = namespace {
-            format!("{}\\{}", ns, name)
-        } else {
-            name.to_string()
+        for reference in model.references() {
+            self.add_class_dependency(reference);
         }
     }
 
-    fn resolve_class_name(&self, name: &str, namespace: Option<&str>, imports: &ImportContext) -> String {
-        // Check if it's already fully qualified
-        if name.starts_with('\\') {
-            // Remove leading backslash for consistency
-            return name[1..].to_string();
-        }
-
-        // Check if there's a use statement import for this name
-        if let Some(fqn) = imports.resolve(name) {
-            return fqn.clone();
-        }
-
-        // Otherwise, resolve relative to current namespace
-        if let Some(ns) = namespace {
-            format!("{}\\{}", ns, name)
-        } else {
-            name.to_string()
-        }
-    }
-
-    fn is_class_type(&self, type_name: &str) -> bool {
-        // Filter out built-in types
-        !matches!(
-            type_name.to_lowercase().as_str(),
-            "int" | "float" | "string" | "bool" | "array" | "object"
-            | "callable" | "iterable" | "void" | "mixed" | "never"
-            | "true" | "false" | "null" | "self" | "parent" | "static"
-        )
-    }
-
-    fn add_class_dependency(&mut self, from: &str, to: &str) {
-        self.class_dependencies
-            .entry(from.to_string())
-            .or_insert_with(HashSet::new)
-            .insert(to.to_string());
-    }
-}
-
-impl GraphAnalyzer for NamespaceDependencyAnalyzer {
-    fn analyze(&mut self, file_path: &str, content: &str) -> Result<()> {
-        let arena = Bump::new();
-        let path = Path::new(file_path);
-        let program = parse_php_file(&arena, path, content)?;
-        self.visit_program(program, file_path, None);
-        Ok(())
-    }
-
     fn build_graph(&self, include_external: bool) -> DependencyGraph {
         let mut graph = DependencyGraph::new();
 
         // Build namespace dependencies from class dependencies
-        let mut ns_deps: IndexMap<String, HashSet<String>> = IndexMap::new();
+        let mut ns_deps: IndexMap<String, IndexMap<String, DependencyUsage>> = IndexMap::new();
         for (from_class, to_classes) in &self.class_dependencies {
             if let Some(from_namespace) = self.class_to_namespace.get(from_class) {
-                for to_class in to_classes {
+                for (to_class, usage) in to_classes {
                     // Check if this is an internal or external dependency
                     let to_namespace = if let Some(ns) = self.class_to_namespace.get(to_class) {
                         ns.clone()
+                    } else if builtins::is_builtin(to_class) {
+                        BUILTIN_NAMESPACE.to_string()
+                    } else if let Some(ns) = pear_namespace(to_class).filter(|_| self.pear_namespaces) {
+                        ns
                     } else {
                         // External class - extract namespace from FQN
                         extract_namespace_from_class(to_class)
@@ -394,8 +96,10 @@ impl GraphAnalyzer for NamespaceDependencyAnalyzer {
                     if from_namespace != &to_namespace {
                         ns_deps
                             .entry(from_namespace.clone())
-                            .or_insert_with(HashSet::new)
-                            .insert(to_namespace);
+                            .or_default()
+                            .entry(to_namespace)
+                            .or_default()
+                            .merge(usage);
                     }
                 }
             }
@@ -412,30 +116,40 @@ impl GraphAnalyzer for NamespaceDependencyAnalyzer {
 
             let node = Node::new(namespace.clone(), label)
                 .with_metadata("files", file_count.to_string())
-                .with_metadata("file_list", files.iter().cloned().collect::<Vec<_>>().join(", "))
-                .with_metadata("type", "internal");
+                .with_metadata("file_list", {
+                    let mut file_list: Vec<_> = files.iter().cloned().collect();
+                    file_list.sort();
+                    file_list.join(", ")
+                })
+                .with_metadata("type", "internal")
+                .with_package(self.packages.lookup_namespace(namespace));
             graph.add_node(node);
         }
 
         // Add namespace dependencies as edges
         for (from, deps) in &ns_deps {
-            for to in deps {
+            for (to, usage) in deps {
                 let is_external = !self.namespace_files.contains_key(to);
+                // Built-in references only reach the model when they were asked for
+                let is_builtin = to == BUILTIN_NAMESPACE;
 
-                if include_external || !is_external {
+                if include_external || !is_external || is_builtin {
                     // Add external namespaces as nodes if including external dependencies
-                    if is_external && include_external {
+                    if is_builtin {
+                        graph.add_node(Node::new(to.clone(), "PHP Built-ins").with_metadata("type", "builtin"));
+                    } else if is_external {
                         let label = if to == "\\" {
                             "Global Namespace".to_string()
                         } else {
                             to.clone()
                         };
                         let node = Node::new(to.clone(), label)
-                            .with_metadata("type", "external");
+                            .with_metadata("type", "external")
+                            .with_package(self.packages.lookup_namespace(to));
                         graph.add_node(node);
                     }
 
-                    graph.add_edge(Edge::new(from.clone(), to.clone()));
+                    graph.add_edge(usage.to_edge(from.clone(), to.clone()));
                 }
             }
         }
@@ -449,3 +163,54 @@ impl Default for NamespaceDependencyAnalyzer {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{FileModel, Location, ReferenceKind, Symbol, SymbolKind};
+
+    #[test]
+    fn test_merged_edges_keep_source_order() {
+        let mut model = CodeModel::new();
+        let mut file = FileModel::new("src/A.php");
+        for (line, from, to) in [
+            (3, "App\\A\\Zeta", "Lib\\Y"),
+            (4, "App\\A\\Alpha", "Lib\\X"),
+            (5, "App\\A\\Mid", "Lib\\W"),
+        ] {
+            file.references.push(Reference {
+                from: from.to_string(),
+                to: to.to_string(),
+                kind: ReferenceKind::Instantiation,
+                method: Some("run".to_string()),
+                location: Location {
+                    file: "src/A.php".to_string(),
+                    line,
+                    column: 1,
+                },
+                unqualified: false,
+            });
+        }
+        for class in ["App\\A\\Zeta", "App\\A\\Alpha", "App\\A\\Mid"] {
+            file.symbols.push(Symbol {
+                fqn: class.to_string(),
+                kind: SymbolKind::Class,
+                namespace: Some("App\\A".to_string()),
+                file: "src/A.php".to_string(),
+                line: 1,
+                methods: Vec::new(),
+                parents: Vec::new(),
+            });
+        }
+        model.add_file(file);
+
+        let mut analyzer = NamespaceDependencyAnalyzer::new();
+        analyzer.analyze(&model);
+        let graph = analyzer.build_graph(true);
+
+        let edge = graph.edges.iter().find(|e| e.to == "Lib").unwrap();
+        let lines: Vec<_> = edge.references.iter().map(|r| r.location.line).collect();
+        assert_eq!(lines, vec![3, 4, 5]);
+        assert_eq!(edge.metadata["methods"], "3");
+    }
+}
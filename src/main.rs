@@ -1,16 +1,34 @@
 mod analyzer;
 mod graph;
+mod model;
 
-use analyzer::{read_file, PhpFileDiscovery};
-use clap::Parser;
+use analyzer::{
+    cache::{AnalysisCache, DEFAULT_CACHE_DIR},
+    composer::ComposerProject, extractor::extract_file, file_filter::FileFilter,
+    psr4::{Psr4Mapping, Psr4Report}, read_file, PhpFileDiscovery,
+};
+use clap::{builder::PossibleValuesParser, Parser};
 use graph::{
     class_dependency::ClassDependencyAnalyzer,
     namespace_dependency::NamespaceDependencyAnalyzer,
+    file_dependency::FileDependencyAnalyzer,
+    method_dependency::MethodDependencyAnalyzer,
     dot_writer::DotWriter,
     csv_writer::CsvWriter,
+    explain_writer::ExplainWriter,
     module_recommender::ModuleRecommender,
     GraphAnalyzer,
 };
+use analyzer::aliases;
+use analyzer::builtins;
+use analyzer::includes;
+use analyzer::diagnostics::DiagnosticsReport;
+use mago_database::file::{File as SourceFile, FileId, FileType};
+use mago_database::{Database, DatabaseConfiguration, DatabaseReader};
+use model::{CodeModel, FileModel, ReferenceKind};
+use rayon::prelude::*;
+use std::borrow::Cow;
+use std::collections::HashSet;
 use std::fs::File;
 use std::io::BufWriter;
 use std::path::PathBuf;
@@ -21,9 +39,36 @@ use std::path::PathBuf;
 #[command(about = "Analyze PHP monoliths and generate dependency graphs")]
 struct Cli {
     /// Directory or directories containing PHP files to analyze
-    #[arg(required = true)]
+    #[arg(required_unless_present = "composer")]
     paths: Vec<PathBuf>,
 
+    /// composer.json of the project: its autoload roots are analyzed and classes are
+    /// tagged with the Composer package that owns them
+    #[arg(long, value_name = "FILE")]
+    composer: Option<PathBuf>,
+
+    /// Skip `autoload-dev` roots when reading composer.json
+    #[arg(long, requires = "composer")]
+    no_dev: bool,
+
+    /// Only analyze files whose path (relative to the scanned directory) matches one of
+    /// these globs, e.g. `src/**` (repeatable)
+    #[arg(long, value_name = "GLOB")]
+    include: Vec<String>,
+
+    /// Skip files whose path (relative to the scanned directory) matches one of these
+    /// globs, e.g. `**/Fixtures/**` (repeatable)
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// Analyze files ignored by `.gitignore`
+    #[arg(long)]
+    no_gitignore: bool,
+
+    /// Analyze files marked `@generated`
+    #[arg(long)]
+    include_generated: bool,
+
     /// Output file for the DOT graph (default: stdout)
     #[arg(short, long)]
     output: Option<PathBuf>,
@@ -33,7 +78,7 @@ struct Cli {
     graph_name: String,
 
     /// Type of analysis to perform
-    #[arg(short = 't', long, default_value = "class", value_parser = ["class", "namespace", "recommend"])]
+    #[arg(short = 't', long, default_value = "class", value_parser = ["class", "namespace", "method", "file", "recommend", "check-psr4"])]
     analysis_type: String,
 
     /// Output format (dot or csv). CSV outputs edge list only.
@@ -44,6 +89,42 @@ struct Cli {
     #[arg(long)]
     include_external: bool,
 
+    /// Include PHP built-in classes such as `Exception` or `DateTimeImmutable`
+    #[arg(long)]
+    include_builtins: bool,
+
+    /// Treat underscores in global class names as namespace separators, so
+    /// `Billing_Invoice_Generator` is in the `Billing\Invoice` namespace
+    #[arg(long)]
+    pear_namespaces: bool,
+
+    /// JSON file mapping legacy class names to the classes they are aliases of, for
+    /// aliases not declared with `class_alias()` in the analyzed code
+    #[arg(long, value_name = "FILE")]
+    aliases: Option<PathBuf>,
+
+    /// PSR-4 namespace prefix and directory for `-t check-psr4`, e.g. `App\=src/`
+    /// (repeatable). Added to the `psr-4` mapping of `--composer`.
+    #[arg(long, value_name = "PREFIX=DIR", value_parser = Psr4Mapping::parse)]
+    psr4: Vec<Psr4Mapping>,
+
+    /// Write the `-t check-psr4` violations as JSON to this file
+    #[arg(long, value_name = "FILE")]
+    psr4_json: Option<PathBuf>,
+
+    /// Only keep dependencies of these kinds (comma-separated)
+    #[arg(long, value_delimiter = ',', value_parser = PossibleValuesParser::new(ReferenceKind::ALL.map(|kind| kind.name())))]
+    include_kinds: Vec<String>,
+
+    /// Drop dependencies of these kinds (comma-separated)
+    #[arg(long, value_delimiter = ',', value_parser = PossibleValuesParser::new(ReferenceKind::ALL.map(|kind| kind.name())))]
+    exclude_kinds: Vec<String>,
+
+    /// Print every source location behind the dependency from one class (or namespace,
+    /// with `-t namespace`) to another instead of writing the graph
+    #[arg(long, num_args = 2, value_names = ["FROM", "TO"])]
+    explain: Option<Vec<String>>,
+
     /// Minimum recommended module size in classes (for recommend mode)
     #[arg(long, default_value = "5")]
     min_module_size: usize,
@@ -52,6 +133,28 @@ struct Cli {
     #[arg(long, default_value = "100")]
     max_module_size: usize,
 
+    /// Number of files to parse in parallel (default: one per CPU core)
+    #[arg(short = 'j', long, default_value = "0")]
+    jobs: usize,
+
+    /// Directory for the per-file analysis cache
+    #[arg(long, value_name = "DIR", default_value = DEFAULT_CACHE_DIR)]
+    cache_dir: PathBuf,
+
+    /// Parse every file instead of reusing cached results
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Exit with a non-zero status when any file fails to parse, or with
+    /// `-t check-psr4` when any class breaks PSR-4
+    #[arg(long)]
+    strict: bool,
+
+    /// Write diagnostics (parse errors, casing mismatches, unresolved includes, dynamic class sites,
+    /// duplicate definitions, alias usages) as JSON to this file
+    #[arg(long, value_name = "FILE")]
+    diagnostics_json: Option<PathBuf>,
+
     /// Verbose output
     #[arg(short, long)]
     verbose: bool,
@@ -60,9 +163,49 @@ struct Cli {
 fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
+    if cli.explain.is_some() && matches!(cli.analysis_type.as_str(), "recommend" | "method" | "file" | "check-psr4") {
+        eprintln!("Error: --explain requires -t class or -t namespace");
+        std::process::exit(1);
+    }
+
+    let composer = match &cli.composer {
+        Some(path) => Some(ComposerProject::load(path)?),
+        None => None,
+    };
+    let mut psr4_mappings = cli.psr4.clone();
+    if let Some(composer) = &composer {
+        psr4_mappings.extend(composer.psr4.iter().cloned());
+    }
+    if cli.analysis_type == "check-psr4" && psr4_mappings.is_empty() {
+        eprintln!("Error: -t check-psr4 requires --composer or --psr4");
+        std::process::exit(1);
+    }
+    let alias_map = match &cli.aliases {
+        Some(path) => aliases::load_alias_map(path)?,
+        None => Default::default(),
+    };
+
     // Discover PHP files
-    let mut discovery = PhpFileDiscovery::new();
-    for path in &cli.paths {
+    let filter = FileFilter::new()
+        .with_include(&cli.include)?
+        .with_exclude(&cli.exclude)?
+        .respect_gitignore(!cli.no_gitignore)
+        .skip_generated(!cli.include_generated);
+    let mut discovery = PhpFileDiscovery::new().with_filter(filter);
+    let mut paths = cli.paths.clone();
+    if let Some(composer) = &composer {
+        for root in &composer.source_roots {
+            if root.dev && cli.no_dev {
+                continue;
+            }
+            if root.path.exists() {
+                paths.push(root.path.clone());
+            } else if cli.verbose {
+                println!("Skipping missing autoload path: {}", root.path.display());
+            }
+        }
+    }
+    for path in &paths {
         if !path.exists() {
             eprintln!("Error: Path does not exist: {}", path.display());
             std::process::exit(1);
@@ -78,40 +221,204 @@ fn main() -> anyhow::Result<()> {
     let files = discovery.get_files();
     if cli.verbose {
         println!("Found {} PHP files", files.len());
+        for (rule, count) in discovery.filter().excluded() {
+            println!("  Excluded {} by {}", count, rule);
+        }
     }
 
-    // Handle "recommend" mode differently - it generates a text report, not a DOT graph
-    if cli.analysis_type == "recommend" {
-        // For recommendations, we need namespace-level analysis
-        let mut analyzer = NamespaceDependencyAnalyzer::new();
+    // Parse each file once and extract its symbols and references
+    let mut model = CodeModel::new();
+    if let Some(composer) = composer {
+        model.packages = composer.packages;
+    }
+    let cache = if cli.no_cache {
+        None
+    } else {
+        Some(AnalysisCache::open(&cli.cache_dir)?)
+    };
 
-        // Analyze each file
-        for (i, file_path) in files.iter().enumerate() {
-            if cli.verbose {
-                println!("[{}/{}] Analyzing: {}", i + 1, files.len(), file_path.display());
-            }
+    let results = extract_files(files, cli.jobs, cache.as_ref())?;
 
-            match read_file(file_path) {
-                Ok(content) => {
-                    if let Err(e) = analyzer.analyze(&file_path.display().to_string(), &content) {
-                        eprintln!("Warning: Failed to analyze {}: {}", file_path.display(), e);
-                    }
+    let mut cache_hits = 0;
+    for (i, (file_path, result)) in files.iter().zip(results).enumerate() {
+        match result {
+            Ok((file_model, cached)) => {
+                if cli.verbose {
+                    let source = if cached { " (cached)" } else { "" };
+                    println!("[{}/{}] Analyzed: {}{}", i + 1, files.len(), file_path.display(), source);
                 }
-                Err(e) => {
-                    eprintln!("Warning: Failed to read {}: {}", file_path.display(), e);
+                if cached {
+                    cache_hits += 1;
                 }
+                model.add_file(file_model);
             }
+            Err(e) => eprintln!("Warning: {:#}", e),
         }
+    }
+    if cli.verbose && cache.is_some() {
+        println!("Reused {} of {} files from the cache", cache_hits, files.len());
+    }
+
+    // Classes defined more than once are reported, and the graphs pick one of
+    // the definitions. References through class aliases point at the class they
+    // name, and strings naming analyzed classes become references. Class and
+    // function names are case-insensitive, so references take the declared
+    // casing, and trait members count as dependencies of the classes using the
+    // trait. Unimported built-ins such as `Exception` mean the global class;
+    // calls fall back to global functions. Built-ins are hidden unless asked
+    // for, and never take part in module recommendations.
+    model.report_duplicate_definitions();
+    aliases::resolve_aliases(&mut model, &alias_map);
+    model.resolve_class_strings();
+    model.normalize_class_names();
+    model.resolve_function_fallbacks();
+    builtins::apply_fallbacks(&mut model);
+    model.apply_trait_uses();
+    if cli.pear_namespaces {
+        model.apply_pear_namespaces();
+    }
+    if !cli.include_builtins || cli.analysis_type == "recommend" {
+        let defined: HashSet<String> = model.symbols().map(|s| s.fqn.clone()).collect();
+        model.retain_references(|reference| defined.contains(&reference.to) || !builtins::is_builtin(&reference.to));
+    }
+
+    // Filter dependencies by kind
+    let include_kinds: Vec<ReferenceKind> =
+        cli.include_kinds.iter().filter_map(|name| ReferenceKind::from_name(name)).collect();
+    let exclude_kinds: Vec<ReferenceKind> =
+        cli.exclude_kinds.iter().filter_map(|name| ReferenceKind::from_name(name)).collect();
+    if !include_kinds.is_empty() || !exclude_kinds.is_empty() {
+        model.retain_references(|reference| {
+            (include_kinds.is_empty() || include_kinds.contains(&reference.kind))
+                && !exclude_kinds.contains(&reference.kind)
+        });
+    }
+
+    // Includes are only resolved, and unresolved ones only reported, for the file graph
+    if cli.analysis_type == "file" {
+        includes::resolve_includes(&mut model);
+    }
+
+    let strict = cli.strict;
+    let diagnostics_json = cli.diagnostics_json.clone();
+    let psr4_violations = if cli.analysis_type == "check-psr4" {
+        write_psr4_report(&cli, &model, &psr4_mappings)?
+    } else {
+        write_output(cli, &model)?;
+        0
+    };
+
+    // Report parse errors, casing mismatches, unresolved includes, dynamic class
+    // sites, duplicate definitions and alias usages once the output has been written
+    let report = DiagnosticsReport::from_model(&model);
+    if !report.is_empty() {
+        eprint!("{}", report.format_text());
+    }
+    if let Some(path) = diagnostics_json {
+        std::fs::write(&path, report.to_json()?)?;
+    }
+    if strict && report.files_with_errors > 0 {
+        eprintln!("Error: {} file(s) failed to parse (--strict)", report.files_with_errors);
+        std::process::exit(1);
+    }
+    if strict && psr4_violations > 0 {
+        eprintln!("Error: {} PSR-4 violation(s) (--strict)", psr4_violations);
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
 
-        // Build namespace dependency graph (without external dependencies for cleaner analysis)
-        let graph = analyzer.build_graph(false);
+/// Read and extract every file on `jobs` threads (0 for one per core), in the
+/// order given. Each result says whether the model was loaded from the cache.
+fn extract_files(
+    files: &[PathBuf],
+    jobs: usize,
+    cache: Option<&AnalysisCache>,
+) -> anyhow::Result<Vec<anyhow::Result<(FileModel, bool)>>> {
+    // Files are read in parallel and registered in a Mago database, which gives
+    // each one a stable FileId so spans can be mapped back to it
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(jobs).build()?;
+    let sources: Vec<anyhow::Result<SourceFile>> = pool.install(|| {
+        files
+            .par_iter()
+            .map(|file_path| {
+                let content = read_file(file_path)?;
+                Ok(SourceFile::new(
+                    Cow::Owned(file_path.display().to_string()),
+                    FileType::Host,
+                    Some(file_path.clone()),
+                    Cow::Owned(content),
+                ))
+            })
+            .collect()
+    });
 
+    let workspace = std::env::current_dir()?;
+    let mut database = Database::new(DatabaseConfiguration::new(&workspace, vec![], vec![], vec![], vec!["php"]));
+    let file_ids: Vec<anyhow::Result<FileId>> = sources
+        .into_iter()
+        .map(|source| source.map(|file| database.add(file)))
+        .collect();
+    let database = database.read_only();
+
+    // Files are parsed in parallel, each with its own arena, and merged in
+    // discovery order so the output matches a single-threaded run. Files whose
+    // content is unchanged since a previous run are loaded from the cache instead.
+    let results = pool.install(|| {
+        file_ids
+            .par_iter()
+            .map(|file_id| {
+                let file_id = file_id.as_ref().map_err(|e| anyhow::anyhow!("{:#}", e))?;
+                let source = database.get_ref(file_id)?;
+                let key = AnalysisCache::key(&source.name, &source.contents);
+                if let Some(file_model) = cache.and_then(|c| c.get(&key)) {
+                    return Ok((file_model, true));
+                }
+
+                let file_model = extract_file(source);
+                if let Some(cache) = cache {
+                    if let Err(e) = cache.put(&key, &file_model) {
+                        eprintln!("Warning: {:#}", e);
+                    }
+                }
+                Ok((file_model, false))
+            })
+            .collect()
+    });
+
+    Ok(results)
+}
+
+/// Write the PSR-4 conformance report, and its JSON form when asked for.
+/// Returns the number of violations.
+fn write_psr4_report(cli: &Cli, model: &CodeModel, mappings: &[Psr4Mapping]) -> anyhow::Result<usize> {
+    let report = Psr4Report::check(model, mappings, &std::env::current_dir()?);
+    let report_text = report.format_text();
+    if let Some(output_path) = &cli.output {
+        std::fs::write(output_path, report_text)?;
+        if cli.verbose {
+            println!("Report written to: {}", output_path.display());
+        }
+    } else {
+        print!("{}", report_text);
+    }
+    if let Some(path) = &cli.psr4_json {
+        std::fs::write(path, report.to_json()?)?;
+    }
+    Ok(report.violations.len())
+}
+
+/// Write the report, graph or explanation selected on the command line
+fn write_output(cli: Cli, model: &CodeModel) -> anyhow::Result<()> {
+    // Handle "recommend" mode differently - it generates a text report, not a DOT graph
+    if cli.analysis_type == "recommend" {
         if cli.verbose {
             println!("\nAnalyzing modularization opportunities...\n");
         }
 
         // Generate recommendations
-        let mut recommender = ModuleRecommender::new(&graph);
+        let mut recommender = ModuleRecommender::from_model(model);
         recommender.set_size_constraints(cli.min_module_size, cli.max_module_size);
         let report = recommender.generate_report();
 
@@ -130,29 +437,30 @@ fn main() -> anyhow::Result<()> {
         // Standard graph generation mode
         let mut analyzer: Box<dyn GraphAnalyzer> = match cli.analysis_type.as_str() {
             "class" => Box::new(ClassDependencyAnalyzer::new()),
+            "method" => Box::new(MethodDependencyAnalyzer::new()),
+            "file" => Box::new(FileDependencyAnalyzer::new()),
             "namespace" => Box::new(NamespaceDependencyAnalyzer::new()),
             _ => {
                 eprintln!("Unknown analysis type: {}", cli.analysis_type);
                 std::process::exit(1);
             }
         };
+        analyzer.analyze(model);
 
-        // Analyze each file
-        for (i, file_path) in files.iter().enumerate() {
-            if cli.verbose {
-                println!("[{}/{}] Analyzing: {}", i + 1, files.len(), file_path.display());
-            }
-
-            match read_file(file_path) {
-                Ok(content) => {
-                    if let Err(e) = analyzer.analyze(&file_path.display().to_string(), &content) {
-                        eprintln!("Warning: Failed to analyze {}: {}", file_path.display(), e);
-                    }
-                }
-                Err(e) => {
-                    eprintln!("Warning: Failed to read {}: {}", file_path.display(), e);
-                }
+        if let Some(pair) = &cli.explain {
+            // Explain against the full graph so external targets can be explained too
+            let graph = analyzer.build_graph(true);
+            let writer = ExplainWriter::new(&pair[0], &pair[1]);
+            if let Some(output_path) = cli.output {
+                let file = File::create(&output_path)?;
+                let mut buf_writer = BufWriter::new(file);
+                writer.write(&graph, &mut buf_writer)?;
+            } else {
+                let stdout = std::io::stdout();
+                let mut handle = stdout.lock();
+                writer.write(&graph, &mut handle)?;
             }
+            return Ok(());
         }
 
         // Build the dependency graph
@@ -205,3 +513,77 @@ fn main() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extraction_does_not_depend_on_jobs() {
+        let dir = std::env::temp_dir().join("php-modfather-jobs");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let files: Vec<PathBuf> = (0..24)
+            .map(|i| {
+                let path = dir.join(format!("Service{}.php", i));
+                let content = format!(
+                    "<?php\nnamespace App;\nclass Service{} extends Service{} {{\n    public function run(Repo $repo) {{\n        $repo->save(new Entity{}());\n        return helper_{}();\n    }}\n}}\n",
+                    i,
+                    (i + 1) % 24,
+                    i % 5,
+                    i % 3
+                );
+                std::fs::write(&path, content).unwrap();
+                path
+            })
+            .collect();
+
+        let models = |jobs: usize| -> Vec<String> {
+            extract_files(&files, jobs, None)
+                .unwrap()
+                .into_iter()
+                .map(|result| serde_json::to_string(&result.unwrap().0).unwrap())
+                .collect()
+        };
+
+        let sequential = models(1);
+        assert_eq!(sequential.len(), files.len());
+        assert!(sequential[3].contains("Service3.php"));
+        assert_eq!(models(4), sequential);
+        assert_eq!(models(0), sequential);
+    }
+
+    #[test]
+    fn test_unchanged_files_are_loaded_from_the_cache() {
+        let dir = std::env::temp_dir().join("php-modfather-cache-hits");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let files: Vec<PathBuf> = ["User", "Order"]
+            .iter()
+            .map(|name| {
+                let path = dir.join(format!("{}.php", name));
+                std::fs::write(&path, format!("<?php\nclass {} extends Model {{}}\n", name)).unwrap();
+                path
+            })
+            .collect();
+        let cache = AnalysisCache::open(&dir.join("cache")).unwrap();
+
+        let run = || -> Vec<(String, bool)> {
+            extract_files(&files, 2, Some(&cache))
+                .unwrap()
+                .into_iter()
+                .map(|result| {
+                    let (model, cached) = result.unwrap();
+                    (model.symbols[0].fqn.clone(), cached)
+                })
+                .collect()
+        };
+
+        assert_eq!(run(), vec![("User".to_string(), false), ("Order".to_string(), false)]);
+        // Unchanged content is loaded without extracting the file again
+        assert_eq!(run(), vec![("User".to_string(), true), ("Order".to_string(), true)]);
+
+        std::fs::write(&files[1], "<?php\nclass Invoice {}\n").unwrap();
+        assert_eq!(run(), vec![("User".to_string(), true), ("Invoice".to_string(), false)]);
+    }
+}
@@ -0,0 +1,808 @@
+use serde::{Deserialize, Serialize};
+use indexmap::IndexMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+
+/// Target recorded for `parent` inside a trait, which depends on the class using
+/// the trait and is resolved by [`CodeModel::apply_trait_uses`]
+pub const TRAIT_PARENT: &str = "parent";
+
+/// Kind of declaration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SymbolKind {
+    Class,
+    Interface,
+    Trait,
+    Enum,
+    /// A global or namespaced function, named with a trailing `()`
+    Function,
+    /// Top-level code of a file, named after the file
+    Script,
+}
+
+impl SymbolKind {
+    pub fn is_class_like(self) -> bool {
+        matches!(self, SymbolKind::Class | SymbolKind::Interface | SymbolKind::Trait | SymbolKind::Enum)
+    }
+}
+
+/// A class-like, function or script declared in the analyzed code
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Symbol {
+    pub fqn: String,
+    pub kind: SymbolKind,
+    /// Declaring namespace, `None` for the global namespace
+    pub namespace: Option<String>,
+    pub file: String,
+    /// Line of the declared name, 1-based
+    pub line: u32,
+    /// Names of the methods a class-like declares, as written
+    pub methods: Vec<String>,
+    /// Parent class a class extends, or parent interfaces of an interface
+    pub parents: Vec<String>,
+}
+
+/// How a class-like or function is referenced. Variants are ordered from the strongest
+/// coupling (inheritance) to the weakest (docblock types).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ReferenceKind {
+    Extends,
+    Implements,
+    TraitUse,
+    Instantiation,
+    StaticCall,
+    /// A method called on an object whose class is known
+    MethodCall,
+    /// Call to a named function
+    FunctionCall,
+    StaticProperty,
+    ConstantAccess,
+    ParameterHint,
+    ReturnHint,
+    PropertyHint,
+    Instanceof,
+    Catch,
+    Attribute,
+    /// Class named by a string: `Foo::class`, a string literal holding a class
+    /// name, or a callable such as `[Foo::class, 'handle']` or `'Foo::handle'`
+    Dynamic,
+    /// Type named in a PHPDoc tag such as `@param`, `@return` or `@var`
+    Docblock,
+}
+
+impl ReferenceKind {
+    pub const ALL: [ReferenceKind; 17] = [
+        ReferenceKind::Extends,
+        ReferenceKind::Implements,
+        ReferenceKind::TraitUse,
+        ReferenceKind::Instantiation,
+        ReferenceKind::StaticCall,
+        ReferenceKind::MethodCall,
+        ReferenceKind::FunctionCall,
+        ReferenceKind::StaticProperty,
+        ReferenceKind::ConstantAccess,
+        ReferenceKind::ParameterHint,
+        ReferenceKind::ReturnHint,
+        ReferenceKind::PropertyHint,
+        ReferenceKind::Instanceof,
+        ReferenceKind::Catch,
+        ReferenceKind::Attribute,
+        ReferenceKind::Dynamic,
+        ReferenceKind::Docblock,
+    ];
+
+    /// Name used on the command line and in DOT/CSV output
+    pub fn name(&self) -> &'static str {
+        match self {
+            ReferenceKind::Extends => "extends",
+            ReferenceKind::Implements => "implements",
+            ReferenceKind::TraitUse => "trait",
+            ReferenceKind::Instantiation => "new",
+            ReferenceKind::StaticCall => "static-call",
+            ReferenceKind::MethodCall => "method-call",
+            ReferenceKind::FunctionCall => "call",
+            ReferenceKind::StaticProperty => "static-property",
+            ReferenceKind::ConstantAccess => "constant",
+            ReferenceKind::ParameterHint => "param-hint",
+            ReferenceKind::ReturnHint => "return-hint",
+            ReferenceKind::PropertyHint => "property-hint",
+            ReferenceKind::Instanceof => "instanceof",
+            ReferenceKind::Catch => "catch",
+            ReferenceKind::Attribute => "attribute",
+            ReferenceKind::Dynamic => "dynamic",
+            ReferenceKind::Docblock => "docblock",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    /// Join a set of kinds into the `|`-separated form used for edge metadata,
+    /// strongest kind first
+    pub fn join<'a>(kinds: impl IntoIterator<Item = &'a ReferenceKind>) -> String {
+        kinds
+            .into_iter()
+            .map(|kind| kind.name())
+            .collect::<Vec<_>>()
+            .join("|")
+    }
+}
+
+/// Where a reference occurs in the source; line and column are 1-based
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Location {
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+/// A reference from a declared symbol to another class-like, resolved to its FQN
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Reference {
+    pub from: String,
+    pub to: String,
+    pub kind: ReferenceKind,
+    /// Method of `from` the reference occurs in, `None` for class-level references
+    pub method: Option<String>,
+    pub location: Location,
+    /// Whether a function call names the function without a namespace, the only
+    /// calls PHP falls back to the global function for
+    #[serde(default)]
+    pub unqualified: bool,
+}
+
+/// What a `use` import brings into scope
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ImportKind {
+    Class,
+    Function,
+    Const,
+}
+
+/// A `use` import and the namespace scope it applies to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Import {
+    pub namespace: Option<String>,
+    pub kind: ImportKind,
+    pub alias: String,
+    pub fqn: String,
+}
+
+/// Which construct pulls in another file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IncludeKind {
+    Include,
+    IncludeOnce,
+    Require,
+    RequireOnce,
+}
+
+impl IncludeKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            IncludeKind::Include => "include",
+            IncludeKind::IncludeOnce => "include_once",
+            IncludeKind::Require => "require",
+            IncludeKind::RequireOnce => "require_once",
+        }
+    }
+}
+
+/// `class_alias('New\Name', 'Old_Name')`: a second name for a class
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClassAlias {
+    pub alias: String,
+    pub class: String,
+    pub location: Location,
+}
+
+/// A reference written against an alias rather than the class it names,
+/// found by `aliases::resolve_aliases`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AliasUsage {
+    /// Symbol the reference is made from
+    pub from: String,
+    pub alias: String,
+    pub class: String,
+    pub location: Location,
+}
+
+/// A call to a method of a known class, an edge of the method call graph
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MethodCall {
+    /// Calling method as `Class::method`, or the function or script making the call
+    pub from: String,
+    /// Called method as `Class::method`, with the class the call was written
+    /// against, which may inherit the method
+    pub to: String,
+    pub location: Location,
+}
+
+/// An `include`/`require` of another file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Include {
+    pub kind: IncludeKind,
+    /// Path of the included file as far as it can be worked out from the source,
+    /// before normalization, e.g. `./src/../lib/db.php`. `None` when the path is
+    /// only known at runtime.
+    pub path: Option<String>,
+    /// The included expression as written, for reporting
+    pub expression: String,
+    /// The analyzed or on-disk file the path resolved to, filled in by
+    /// `includes::resolve_includes`
+    #[serde(skip)]
+    pub resolved: Option<String>,
+    pub location: Location,
+}
+
+/// What a diagnostic is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiagnosticKind {
+    /// A syntax error. The AST of the file is partial, so its references may be incomplete.
+    ParseError,
+    /// A class referenced with different casing than it is declared with, which
+    /// PHP accepts but case-sensitive autoloaders do not
+    CaseMismatch,
+    /// An `include`/`require` whose path is built at runtime or names a file
+    /// that does not exist
+    UnresolvedInclude,
+    /// `new $class` or `$class::method()`, whose class is only known at runtime
+    DynamicClass,
+    /// A class-like or function declared in more than one place
+    DuplicateDefinition,
+}
+
+/// A problem found in a file, such as a syntax error
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    pub location: Location,
+    pub message: String,
+}
+
+/// Everything extracted from a single PHP file. Cached on disk as JSON; the
+/// cache key hashes this module's source, so changing its shape invalidates it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FileModel {
+    pub path: String,
+    pub symbols: Vec<Symbol>,
+    pub references: Vec<Reference>,
+    pub imports: Vec<Import>,
+    pub includes: Vec<Include>,
+    pub calls: Vec<MethodCall>,
+    /// String literals that may name a class, as `dynamic` references. Only those
+    /// naming an analyzed class are kept, by `CodeModel::resolve_class_strings`.
+    pub class_strings: Vec<Reference>,
+    pub aliases: Vec<ClassAlias>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl FileModel {
+    pub fn new(path: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            ..Self::default()
+        }
+    }
+}
+
+/// A Composer package that owns part of the class namespace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Package {
+    pub name: String,
+    /// Only required for development (`require-dev` / `autoload-dev`)
+    pub dev: bool,
+}
+
+impl Package {
+    pub fn scope(&self) -> &'static str {
+        if self.dev {
+            "dev"
+        } else {
+            "prod"
+        }
+    }
+}
+
+/// Maps PSR-4/PSR-0 namespace prefixes to the packages that autoload them
+#[derive(Debug, Clone, Default)]
+pub struct PackageIndex {
+    /// Namespace prefix (always ending in `\`, empty for a fallback root) -> package
+    prefixes: Vec<(String, Package)>,
+}
+
+impl PackageIndex {
+    pub fn add_prefix(&mut self, prefix: &str, package: Package) {
+        let mut prefix = prefix.trim_start_matches('\\').to_string();
+        if !prefix.is_empty() && !prefix.ends_with('\\') {
+            prefix.push('\\');
+        }
+        self.prefixes.push((prefix, package));
+    }
+
+    /// Package owning a class (or, with a trailing `\`, a namespace), by longest prefix
+    pub fn lookup(&self, name: &str) -> Option<&Package> {
+        self.prefixes
+            .iter()
+            .filter(|(prefix, _)| name.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, package)| package)
+    }
+
+    /// Package owning every class in a namespace
+    pub fn lookup_namespace(&self, namespace: &str) -> Option<&Package> {
+        if namespace == "\\" {
+            return self.lookup("");
+        }
+        self.lookup(&format!("{}\\", namespace))
+    }
+}
+
+/// Whether a class-like or function is declared in a file named after it, as
+/// autoloaders expect
+fn is_named_after(symbol: &Symbol) -> bool {
+    let name = symbol.fqn.trim_end_matches("()");
+    let short_name = name.rsplit('\\').next().unwrap_or(name);
+    Path::new(&symbol.file).file_stem().is_some_and(|stem| stem == short_name)
+}
+
+/// Namespace implied by a PEAR/Zend-style class name, written with backslashes so
+/// it lines up with real namespaces: `Billing_Invoice_Generator` is in `Billing\Invoice`.
+/// `None` for namespaced names, functions, and names without a usable prefix.
+pub fn pear_namespace(fqn: &str) -> Option<String> {
+    if fqn.contains('\\') || fqn.ends_with("()") {
+        return None;
+    }
+    let (prefix, name) = fqn.rsplit_once('_')?;
+    if name.is_empty() || prefix.split('_').any(str::is_empty) {
+        return None;
+    }
+    Some(prefix.replace('_', "\\"))
+}
+
+/// Code model of the analyzed code base, built from one extraction pass per file
+#[derive(Debug, Default)]
+pub struct CodeModel {
+    pub files: Vec<FileModel>,
+    /// Composer packages, empty unless a `composer.json` was given
+    pub packages: PackageIndex,
+    /// Whether underscore-separated global class names are split into
+    /// pseudo-namespaces, set by `apply_pear_namespaces`
+    pub pear_namespaces: bool,
+    /// References made through class aliases, filled in by `aliases::resolve_aliases`
+    pub alias_usages: Vec<AliasUsage>,
+}
+
+impl CodeModel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_file(&mut self, file: FileModel) {
+        self.files.push(file);
+    }
+
+    /// All declared symbols, in file order
+    pub fn symbols(&self) -> impl Iterator<Item = &Symbol> {
+        self.files.iter().flat_map(|f| f.symbols.iter())
+    }
+
+    /// All diagnostics, in file order
+    pub fn diagnostics(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.files.iter().flat_map(|f| f.diagnostics.iter())
+    }
+
+    /// All extracted references, in file order
+    pub fn references(&self) -> impl Iterator<Item = &Reference> {
+        self.files.iter().flat_map(|f| f.references.iter())
+    }
+
+    /// Drop every reference for which `keep` returns false
+    pub fn retain_references(&mut self, mut keep: impl FnMut(&Reference) -> bool) {
+        for file in &mut self.files {
+            file.references.retain(&mut keep);
+        }
+    }
+
+    /// Copy the references made inside each trait to every class using it, directly
+    /// or through other traits, with `self`/`static` pointing at the using class and
+    /// `parent` at its parent. The copies keep their location in the trait.
+    pub fn apply_trait_uses(&mut self) {
+        let traits: HashSet<String> = self
+            .symbols()
+            .filter(|s| s.kind == SymbolKind::Trait)
+            .map(|s| s.fqn.clone())
+            .collect();
+        let mut trait_references: HashMap<String, Vec<Reference>> = HashMap::new();
+        for reference in self.references() {
+            if traits.contains(&reference.from) {
+                trait_references
+                    .entry(reference.from.clone())
+                    .or_default()
+                    .push(reference.clone());
+            }
+        }
+
+        for file in &mut self.files {
+            let mut copies = Vec::new();
+            for reference in &file.references {
+                if reference.kind != ReferenceKind::TraitUse
+                    || traits.contains(&reference.from)
+                    || !traits.contains(&reference.to)
+                {
+                    continue;
+                }
+
+                let class = &reference.from;
+                // The parent the class declares, from the definition in this file
+                let parent = file
+                    .symbols
+                    .iter()
+                    .find(|symbol| symbol.fqn == *class)
+                    .and_then(|symbol| symbol.parents.first());
+                let mut pending = vec![reference.to.clone()];
+                let mut seen: HashSet<String> = HashSet::new();
+                while let Some(trait_name) = pending.pop() {
+                    if !seen.insert(trait_name.clone()) {
+                        continue;
+                    }
+                    for used in trait_references.get(&trait_name).into_iter().flatten() {
+                        let to = if used.to == trait_name {
+                            class.clone()
+                        } else if used.to == TRAIT_PARENT {
+                            match parent {
+                                Some(parent) => parent.clone(),
+                                None => continue,
+                            }
+                        } else {
+                            used.to.clone()
+                        };
+                        if used.kind == ReferenceKind::TraitUse && traits.contains(&to) {
+                            pending.push(to.clone());
+                        }
+                        copies.push(Reference {
+                            from: class.clone(),
+                            to,
+                            ..used.clone()
+                        });
+                    }
+                }
+            }
+            file.references.extend(copies);
+        }
+
+        // `parent` cannot be resolved for the trait itself
+        self.retain_references(|r| r.to != TRAIT_PARENT);
+    }
+
+    /// Every declared symbol with all of its definitions, keyed by the name the
+    /// graphs use. Class-likes and functions declared more than once, such as
+    /// polyfills or conditionally declared classes, have the definition the graphs
+    /// use first: the one in a file named after it (`User.php` for `App\User`),
+    /// then the first by path.
+    pub fn definitions(&self) -> IndexMap<&str, Vec<&Symbol>> {
+        let mut definitions: IndexMap<String, Vec<&Symbol>> = IndexMap::new();
+        for symbol in self.symbols() {
+            // Class and function names are case-insensitive, script paths are not
+            let key = match symbol.kind {
+                SymbolKind::Script => symbol.fqn.clone(),
+                _ => symbol.fqn.to_lowercase(),
+            };
+            definitions.entry(key).or_default().push(symbol);
+        }
+
+        definitions
+            .into_values()
+            .map(|mut symbols| {
+                symbols.sort_by_key(|symbol| (!is_named_after(symbol), symbol.file.as_str(), symbol.line));
+                (symbols[0].fqn.as_str(), symbols)
+            })
+            .collect()
+    }
+
+    /// Report every class-like or function declared more than once, listing all
+    /// of its definitions, at the definition the graphs use
+    pub fn report_duplicate_definitions(&mut self) {
+        let mut duplicates: Vec<Diagnostic> = Vec::new();
+        for (name, symbols) in self.definitions() {
+            if symbols.len() < 2 {
+                continue;
+            }
+            let others: Vec<String> = symbols[1..]
+                .iter()
+                .map(|symbol| format!("{}:{}", symbol.file, symbol.line))
+                .collect();
+            duplicates.push(Diagnostic {
+                kind: DiagnosticKind::DuplicateDefinition,
+                location: Location {
+                    file: symbols[0].file.clone(),
+                    line: symbols[0].line,
+                    column: 1,
+                },
+                message: format!(
+                    "`{}` is defined {} times; the graphs use this definition, also defined at {}",
+                    name,
+                    symbols.len(),
+                    others.join(", ")
+                ),
+            });
+        }
+
+        for diagnostic in duplicates {
+            if let Some(file) = self.files.iter_mut().find(|file| file.path == diagnostic.location.file) {
+                file.diagnostics.push(diagnostic);
+            }
+        }
+    }
+
+    /// Keep the string literals that name an analyzed class as `dynamic`
+    /// references. Strings naming classes outside the analyzed code cannot be
+    /// told apart from other text, so they are dropped.
+    pub fn resolve_class_strings(&mut self) {
+        let declared: HashSet<String> = self
+            .symbols()
+            .filter(|s| s.kind.is_class_like())
+            .map(|s| s.fqn.to_lowercase())
+            .collect();
+
+        for file in &mut self.files {
+            let strings = std::mem::take(&mut file.class_strings);
+            file.references.extend(
+                strings
+                    .into_iter()
+                    .filter(|reference| declared.contains(&reference.to.to_lowercase())),
+            );
+        }
+    }
+
+    /// Point references and method calls at the declared casing of the classes
+    /// they name, as PHP class names are case-insensitive, and report every
+    /// reference written with a different casing
+    pub fn normalize_class_names(&mut self) {
+        // The casing of the definition the graphs use, so references and nodes agree
+        let declared: HashMap<String, String> = self
+            .definitions()
+            .into_iter()
+            .filter(|(_, symbols)| symbols[0].kind.is_class_like())
+            .map(|(name, _)| (name.to_lowercase(), name.to_string()))
+            .collect();
+
+        for file in &mut self.files {
+            // Declared parents are reported through their `extends` references
+            for parent in file.symbols.iter_mut().flat_map(|symbol| symbol.parents.iter_mut()) {
+                if let Some(canonical) = declared.get(&parent.to_lowercase()) {
+                    *parent = canonical.clone();
+                }
+            }
+            for reference in &mut file.references {
+                if let Some(canonical) = declared.get(&reference.from.to_lowercase()) {
+                    reference.from = canonical.clone();
+                }
+                if reference.kind == ReferenceKind::FunctionCall {
+                    continue;
+                }
+                let Some(canonical) = declared.get(&reference.to.to_lowercase()) else {
+                    continue;
+                };
+                if *canonical != reference.to {
+                    file.diagnostics.push(Diagnostic {
+                        kind: DiagnosticKind::CaseMismatch,
+                        location: reference.location.clone(),
+                        message: format!("`{}` is declared as `{}`", reference.to, canonical),
+                    });
+                    reference.to = canonical.clone();
+                }
+            }
+            // Call edges repeat the class names of their references, already reported
+            for call in &mut file.calls {
+                for name in [&mut call.from, &mut call.to] {
+                    let Some((class, method)) = name.split_once("::") else {
+                        continue;
+                    };
+                    if let Some(canonical) = declared.get(&class.to_lowercase()) {
+                        *name = format!("{}::{}", canonical, method);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Place class-likes in the global namespace whose names follow the PEAR/Zend
+    /// convention in the pseudo-namespace their prefix implies, so pre-namespace
+    /// code takes part in namespace graphs and module recommendations
+    pub fn apply_pear_namespaces(&mut self) {
+        self.pear_namespaces = true;
+        for file in &mut self.files {
+            for symbol in &mut file.symbols {
+                if symbol.namespace.is_none() && symbol.kind.is_class_like() {
+                    symbol.namespace = pear_namespace(&symbol.fqn);
+                }
+            }
+        }
+    }
+
+    /// Resolve function calls against the declared functions, case-insensitively.
+    /// An unqualified call whose function the caller's namespace does not declare
+    /// falls back to the global function, as PHP does. Qualified calls never do.
+    pub fn resolve_function_fallbacks(&mut self) {
+        let functions: HashMap<String, String> = self
+            .definitions()
+            .into_iter()
+            .filter(|(_, symbols)| symbols[0].kind == SymbolKind::Function)
+            .map(|(name, _)| (name.to_lowercase(), name.to_string()))
+            .collect();
+        let namespaces: HashMap<String, Option<String>> = self
+            .symbols()
+            .map(|symbol| (symbol.fqn.clone(), symbol.namespace.clone()))
+            .collect();
+
+        for file in &mut self.files {
+            for reference in &mut file.references {
+                if reference.kind != ReferenceKind::FunctionCall {
+                    continue;
+                }
+                if let Some(canonical) = functions.get(&reference.to.to_lowercase()) {
+                    reference.to = canonical.clone();
+                    continue;
+                }
+                if !reference.unqualified {
+                    continue;
+                }
+                let Some((namespace, name)) = reference.to.rsplit_once('\\') else {
+                    continue;
+                };
+                let caller_namespace = namespaces.get(&reference.from).and_then(|ns| ns.as_deref());
+                if caller_namespace == Some(namespace) {
+                    reference.to = functions
+                        .get(&name.to_lowercase())
+                        .cloned()
+                        .unwrap_or_else(|| name.to_string());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pear_namespaces() {
+        assert_eq!(pear_namespace("Billing_Invoice_Generator").as_deref(), Some("Billing\\Invoice"));
+        assert_eq!(pear_namespace("Zend_Db").as_deref(), Some("Zend"));
+        assert_eq!(pear_namespace("Invoice"), None);
+        assert_eq!(pear_namespace("_Private_Thing"), None);
+        assert_eq!(pear_namespace("Billing__Invoice"), None);
+        assert_eq!(pear_namespace("App\\Legacy_Thing"), None);
+        assert_eq!(pear_namespace("format_money()"), None);
+    }
+
+    #[test]
+    fn test_normalizes_class_name_casing() {
+        let mut user = FileModel::new("src/User.php");
+        user.symbols.push(Symbol {
+            fqn: "App\\User".to_string(),
+            kind: SymbolKind::Class,
+            namespace: Some("App".to_string()),
+            file: "src/User.php".to_string(),
+            line: 3,
+            methods: Vec::new(),
+            parents: Vec::new(),
+        });
+        let mut controller = FileModel::new("src/Controller.php");
+        for (to, line) in [("App\\user", 5), ("App\\User", 6), ("App\\Other", 7)] {
+            controller.references.push(Reference {
+                from: "App\\Controller".to_string(),
+                to: to.to_string(),
+                kind: ReferenceKind::Instantiation,
+                method: None,
+                location: Location {
+                    file: "src/Controller.php".to_string(),
+                    line,
+                    column: 9,
+                },
+                unqualified: false,
+            });
+        }
+        let mut model = CodeModel::new();
+        model.add_file(user);
+        model.add_file(controller);
+
+        model.normalize_class_names();
+
+        let targets: Vec<_> = model.references().map(|r| r.to.as_str()).collect();
+        assert_eq!(targets, vec!["App\\User", "App\\User", "App\\Other"]);
+        let diagnostics: Vec<_> = model.diagnostics().collect();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::CaseMismatch);
+        assert_eq!(diagnostics[0].location.line, 5);
+        assert_eq!(diagnostics[0].message, "`App\\user` is declared as `App\\User`");
+    }
+
+    #[test]
+    fn test_reports_duplicate_definitions() {
+        let mut model = CodeModel::new();
+        for (path, fqn, line) in [
+            ("polyfill/bootstrap.php", "Stringable", 4),
+            ("src/Stringable.php", "Stringable", 3),
+            ("legacy/b.php", "stringable", 9),
+            ("src/User.php", "App\\User", 5),
+        ] {
+            let mut file = FileModel::new(path);
+            file.symbols.push(Symbol {
+                fqn: fqn.to_string(),
+                kind: SymbolKind::Interface,
+                namespace: None,
+                file: path.to_string(),
+                line,
+                methods: Vec::new(),
+                parents: Vec::new(),
+            });
+            model.add_file(file);
+        }
+
+        let definitions = model.definitions();
+        let files: Vec<_> = definitions["Stringable"].iter().map(|s| s.file.as_str()).collect();
+        assert_eq!(files, vec!["src/Stringable.php", "legacy/b.php", "polyfill/bootstrap.php"]);
+        assert_eq!(definitions.len(), 2);
+
+        model.report_duplicate_definitions();
+        let diagnostics: Vec<_> = model.diagnostics().collect();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].location.to_string(), "src/Stringable.php:3:1");
+        assert_eq!(
+            diagnostics[0].message,
+            "`Stringable` is defined 3 times; the graphs use this definition, also defined at \
+             legacy/b.php:9, polyfill/bootstrap.php:4"
+        );
+    }
+
+    #[test]
+    fn test_casing_follows_the_definition_the_graphs_use() {
+        let mut model = CodeModel::new();
+        for (path, fqn) in [("a/poly.php", "stringable"), ("src/Stringable.php", "Stringable")] {
+            let mut file = FileModel::new(path);
+            file.symbols.push(Symbol {
+                fqn: fqn.to_string(),
+                kind: SymbolKind::Interface,
+                namespace: None,
+                file: path.to_string(),
+                line: 2,
+                methods: Vec::new(),
+                parents: Vec::new(),
+            });
+            model.add_file(file);
+        }
+        let mut user = FileModel::new("src/User.php");
+        user.references.push(Reference {
+            from: "App\\User".to_string(),
+            to: "stringable".to_string(),
+            kind: ReferenceKind::Implements,
+            method: None,
+            location: Location {
+                file: "src/User.php".to_string(),
+                line: 3,
+                column: 23,
+            },
+            unqualified: false,
+        });
+        model.add_file(user);
+
+        model.normalize_class_names();
+
+        let nodes: Vec<_> = model.definitions().into_keys().collect();
+        assert_eq!(nodes, vec!["Stringable"]);
+        assert_eq!(model.files[2].references[0].to, "Stringable");
+    }
+}